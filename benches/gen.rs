@@ -0,0 +1,118 @@
+use std::hint::black_box;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use snowflake::{
+    decode, decode_many, AtomicSnowflake, CachedClock, CoarseClock, ConcurrentSnowflake, Snowflake,
+    SnowflakeLayout, WaitStrategy,
+};
+
+fn bench_gen(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gen");
+
+    let mut snowflake = Snowflake::new(0).unwrap();
+    group.bench_function("system clock", |b| b.iter(|| snowflake.gen().unwrap()));
+
+    let mut snowflake = Snowflake::builder(0)
+        .clock(CachedClock::new())
+        .build()
+        .unwrap();
+    group.bench_function("cached clock", |b| b.iter(|| snowflake.gen().unwrap()));
+
+    let mut snowflake = Snowflake::builder(0).clock(CoarseClock).build().unwrap();
+    group.bench_function("coarse clock", |b| b.iter(|| snowflake.gen().unwrap()));
+
+    group.finish();
+}
+
+// with only 16 ids per millisecond nearly every id generated waits for the clock,
+// so this measures how quickly each strategy gets to the next millisecond
+fn bench_exhaustion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("exhaustion");
+    for (name, wait_strategy) in [
+        ("sleep", WaitStrategy::Sleep),
+        ("spin", WaitStrategy::Spin),
+        (
+            "spin then sleep",
+            WaitStrategy::SpinThenSleep {
+                spin_for: Duration::from_micros(100),
+            },
+        ),
+    ] {
+        let mut snowflake = Snowflake::builder(0)
+            .layout(SnowflakeLayout::new(44, 4, 2).unwrap())
+            .wait_strategy(wait_strategy)
+            .build()
+            .unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for _ in 0..16 {
+                    snowflake.gen().unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+// uncontended, so this measures what taking the lock costs against a compare-and-swap
+fn bench_shared(c: &mut Criterion) {
+    let mut group = c.benchmark_group("shared");
+
+    let mut snowflake = ConcurrentSnowflake::new(0).unwrap();
+    group.bench_function("mutex", |b| b.iter(|| snowflake.gen().unwrap()));
+
+    let snowflake = AtomicSnowflake::new(0).unwrap();
+    group.bench_function("atomic", |b| b.iter(|| snowflake.gen().unwrap()));
+
+    group.finish();
+}
+
+// a batch for one bulk insert, from a lock and clock read per id against one lock
+// for the batch and a clock read per millisecond
+fn bench_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch");
+    let mut snowflake = ConcurrentSnowflake::new(0).unwrap();
+    group.bench_function("gen", |b| {
+        b.iter(|| {
+            (0..10_000)
+                .map(|_| snowflake.gen().unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("gen_many", |b| {
+        b.iter(|| snowflake.gen_many(10_000).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    let mut snowflake = Snowflake::new(0).unwrap();
+    let ids: Vec<i64> = (0..100_000).map(|_| snowflake.gen().unwrap()).collect();
+    let mut ts = vec![0; ids.len()];
+    let mut seq = vec![0; ids.len()];
+    let mut svc = vec![0; ids.len()];
+
+    group.bench_function("per id", |b| {
+        b.iter(|| {
+            for (i, id) in black_box(&ids).iter().enumerate() {
+                (ts[i], seq[i], svc[i]) = decode(*id);
+            }
+        })
+    });
+    group.bench_function("decode_many", |b| {
+        b.iter(|| decode_many(black_box(&ids), &mut ts, &mut seq, &mut svc))
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_gen,
+    bench_exhaustion,
+    bench_shared,
+    bench_batch,
+    bench_decode
+);
+criterion_main!(benches);
@@ -0,0 +1,181 @@
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::{AsyncIdProvider, ConcurrentSnowflakeError, IdFuture};
+use crate::{Clock, Snowflake, SnowflakeError};
+
+/*
+
+a generator for async code running on tokio, where gen waiting for the clock
+with a blocking sleep would stall a whole worker thread along with every task
+on it, instead every wait for the next millisecond, or for the clock to catch up
+within max_backward_skew, is a tokio::time::sleep that yields to other tasks
+
+it waits on tokio's timer, and not with the clock's sleep, so a MockClock has to
+be advanced by the test itself, and the wait strategy doesn't apply as there's
+never anything to spin on, max_clock_stall still does
+
+as an AsyncIdProvider it can be shared between tasks, each next_id only holds the
+lock while generating, never across a wait, so tasks waiting for the clock don't
+keep the others from generating until they're done, and a task that panicked
+while generating poisons it like a ConcurrentSnowflake with PoisonPolicy::Propagate
+
+*/
+
+#[derive(Debug)]
+pub struct AsyncSnowflake<C> {
+    snowflake: Mutex<Snowflake<C>>,
+}
+
+impl<C: Clock> AsyncSnowflake<C> {
+    pub fn new(snowflake: Snowflake<C>) -> Self {
+        Self {
+            snowflake: Mutex::new(snowflake),
+        }
+    }
+
+    pub async fn gen(&mut self) -> Result<i64, SnowflakeError> {
+        // &mut self means nothing holds the lock now, it's only poisoned if an earlier
+        // next_id panicked while generating, and the generator is recovered as it was
+        // left, like a ConcurrentSnowflake with PoisonPolicy::Recover
+        let snowflake = self
+            .snowflake
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut waited = Duration::ZERO;
+        while let Some(wait) = snowflake.pending_wait()? {
+            check_stall(snowflake, waited)?;
+            tokio::time::sleep(wait).await;
+            waited += wait;
+        }
+        snowflake.gen()
+    }
+
+    pub fn into_inner(self) -> Snowflake<C> {
+        self.snowflake
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// sleeps on tokio's timer like gen, instead of blocking the executor
+impl<C: Clock + Send> AsyncIdProvider for AsyncSnowflake<C> {
+    fn next_id(&self) -> IdFuture<'_> {
+        Box::pin(async move {
+            let mut waited = Duration::ZERO;
+            loop {
+                let wait = {
+                    let mut snowflake = self
+                        .snowflake
+                        .lock()
+                        .map_err(|_| ConcurrentSnowflakeError::PoisonError)?;
+                    match snowflake.pending_wait()? {
+                        Some(wait) => {
+                            check_stall(&snowflake, waited)?;
+                            wait
+                        }
+                        None => return Ok(snowflake.gen()?),
+                    }
+                };
+                tokio::time::sleep(wait).await;
+                waited += wait;
+            }
+        })
+    }
+}
+
+fn check_stall<C>(snowflake: &Snowflake<C>, waited: Duration) -> Result<(), SnowflakeError> {
+    if snowflake.max_clock_stall.is_some_and(|max| waited >= max) {
+        return Err(SnowflakeError::ClockStalledError { waited });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockClock, SnowflakeLayout};
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    fn tiny_layout() -> SnowflakeLayout {
+        SnowflakeLayout::new(44, 2, 10).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_async_snowflake_waits_for_the_next_millisecond() {
+        let snowflake = Snowflake::builder(1).layout(tiny_layout()).build().unwrap();
+        let mut snowflake = AsyncSnowflake::new(snowflake);
+        let mut ids = HashSet::new();
+        for _ in 0..40 {
+            assert!(ids.insert(snowflake.gen().await.unwrap()));
+        }
+        let millis: HashSet<_> = ids
+            .iter()
+            .map(|id| tiny_layout().timestamp_bits_of(*id))
+            .collect();
+        assert!(millis.len() >= 10);
+    }
+
+    #[tokio::test]
+    async fn test_async_snowflake_yields_while_waiting() {
+        let clock = MockClock::frozen(Duration::from_millis(1_000));
+        let snowflake = Snowflake::builder(1)
+            .clock(clock.clone())
+            .layout(tiny_layout())
+            .build()
+            .unwrap();
+        let mut snowflake = AsyncSnowflake::new(snowflake);
+        for _ in 0..4 {
+            snowflake.gen().await.unwrap();
+        }
+        // a blocking wait on a frozen clock would never let the timeout fire
+        let waiting = tokio::time::timeout(Duration::from_millis(20), snowflake.gen()).await;
+        assert!(waiting.is_err());
+
+        clock.advance(Duration::from_millis(1));
+        let id = snowflake.gen().await.unwrap();
+        assert_eq!(tiny_layout().decode(id), (1_001, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_async_snowflake_provides_ids_without_blocking() {
+        let clock = MockClock::frozen(Duration::from_millis(1_000));
+        let snowflake = Snowflake::builder(1)
+            .clock(clock.clone())
+            .layout(tiny_layout())
+            .build()
+            .unwrap();
+        let provider: Arc<dyn AsyncIdProvider> = Arc::new(AsyncSnowflake::new(snowflake));
+        for seq in 0..4 {
+            let id = provider.next_id().await.unwrap();
+            assert_eq!(tiny_layout().decode(id), (1_000, seq, 1));
+        }
+        // a blocking wait on a frozen clock would never let the timeout fire
+        let waiting = tokio::time::timeout(Duration::from_millis(20), provider.next_id()).await;
+        assert!(waiting.is_err());
+
+        clock.advance(Duration::from_millis(1));
+        let id = provider.next_id().await.unwrap();
+        assert_eq!(tiny_layout().decode(id), (1_001, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_async_snowflake_reports_a_stalled_clock() {
+        let clock = MockClock::frozen(Duration::from_millis(1_000));
+        let snowflake = Snowflake::builder(1)
+            .clock(clock)
+            .layout(tiny_layout())
+            .max_clock_stall(Duration::from_millis(5))
+            .build()
+            .unwrap();
+        let mut snowflake = AsyncSnowflake::new(snowflake);
+        for _ in 0..4 {
+            snowflake.gen().await.unwrap();
+        }
+        assert!(matches!(
+            snowflake.gen().await,
+            Err(SnowflakeError::ClockStalledError { .. })
+        ));
+    }
+}
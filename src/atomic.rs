@@ -0,0 +1,215 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Clock, Snowflake, SnowflakeBuilder, SnowflakeError, SystemClock};
+
+/*
+
+a generator shared between threads like ConcurrentSnowflake, but lock-free, the
+millisecond and sequence of the next id are packed into a single AtomicU64 that
+every gen advances with a compare-and-swap, so a thread only ever retries when
+another one took the id it was about to hand out, and never blocks on another
+thread that holds a lock while sleeping through an exhausted millisecond
+
+the epoch, layout, service id, clock, wait strategy and max_clock_stall of the
+builder apply, the sequence always starts at 0, and the other settings don't,
+most of all the clock going backward is never waited out nor an error, ids keep
+counting from the last millisecond like a Snowflake without max_backward_skew,
+and a timestamp beyond the layout is always a TimestampOverflowError
+
+*/
+
+#[derive(Debug)]
+pub struct AtomicSnowflake<C = SystemClock> {
+    // only its settings are used, its sequence state is never updated
+    snowflake: Snowflake<C>,
+    // the millisecond of the next id shifted above its sequence, a sequence that
+    // ran out carries into the following millisecond, which waits for the clock
+    next: AtomicU64,
+}
+
+impl AtomicSnowflake {
+    pub fn new(service_id: u16) -> Result<Self, SnowflakeError> {
+        Self::with_epoch(service_id, UNIX_EPOCH)
+    }
+
+    pub fn with_epoch(service_id: u16, epoch: SystemTime) -> Result<Self, SnowflakeError> {
+        Snowflake::builder(service_id).epoch(epoch).build_atomic()
+    }
+}
+
+impl<C: Clock> AtomicSnowflake<C> {
+    pub fn gen(&self) -> Result<i64, SnowflakeError> {
+        let layout = self.snowflake.layout;
+        let sequence_bits = layout.sequence_bits();
+        let max_seq = layout.max_sequence() as u64;
+        let mut next = self.next.load(Ordering::Relaxed);
+        loop {
+            let millis = self.snowflake.elapsed()?.as_millis();
+            if millis > layout.max_timestamp() as u128 {
+                return Err(SnowflakeError::TimestampOverflowError {
+                    millis,
+                    max: layout.max_timestamp(),
+                });
+            }
+            let clock_millis = millis as u64;
+            let candidate = next.max(clock_millis << sequence_bits);
+            let candidate_millis = candidate >> sequence_bits;
+            if candidate_millis > clock_millis && candidate & max_seq == 0 {
+                // the sequence of the millisecond before ran out, wait for the clock
+                // to get to this one, unless another thread gets there first
+                if candidate_millis > layout.max_timestamp() as u64 {
+                    return Err(SnowflakeError::TimestampOverflowError {
+                        millis: candidate_millis as u128,
+                        max: layout.max_timestamp(),
+                    });
+                }
+                self.snowflake.wait_for_millis(candidate_millis as i64)?;
+                next = self.next.load(Ordering::Relaxed);
+                continue;
+            }
+            match self.next.compare_exchange_weak(
+                next,
+                candidate + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    return Ok(layout.pack(
+                        candidate_millis as i64,
+                        (candidate & max_seq) as u32,
+                        self.snowflake.service_id,
+                    ))
+                }
+                Err(current) => next = current,
+            }
+        }
+    }
+
+    pub fn into_inner(self) -> Snowflake<C> {
+        let mut snowflake = self.snowflake;
+        let next = self.next.into_inner();
+        let sequence_bits = snowflake.layout.sequence_bits();
+        snowflake.last_millis = (next >> sequence_bits) as i64;
+        snowflake.seq = (next & snowflake.layout.max_sequence() as u64) as u32;
+        if snowflake.seq == 0 && snowflake.last_millis > 0 {
+            // the next millisecond hasn't started, it is the one before that ran out
+            snowflake.last_millis -= 1;
+            snowflake.seq = snowflake.layout.max_sequence() + 1;
+        }
+        snowflake.seq_offset = 0;
+        snowflake
+    }
+}
+
+impl<C: Clock> SnowflakeBuilder<C> {
+    pub fn build_atomic(self) -> Result<AtomicSnowflake<C>, SnowflakeError> {
+        let snowflake = self.build()?;
+        let sequence_bits = snowflake.layout.sequence_bits();
+        let last_millis = snowflake.last_millis as u64;
+        // a restored sequence with a random start can't be carried on from 0, so it
+        // starts over in the next millisecond
+        let next = if snowflake.seq_offset == 0 {
+            (last_millis << sequence_bits) + snowflake.seq as u64
+        } else {
+            (last_millis + 1) << sequence_bits
+        };
+        Ok(AtomicSnowflake {
+            snowflake,
+            next: AtomicU64::new(next),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, MockClock, SnowflakeLayout};
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_atomic_snowflake_generates_unique_ids_across_threads() {
+        let snowflake = Arc::new(AtomicSnowflake::new(1).unwrap());
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let snowflake = Arc::clone(&snowflake);
+                thread::spawn(move || {
+                    (0..10_000)
+                        .map(|_| snowflake.gen().unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut ids = HashSet::new();
+        for handle in handles {
+            for id in handle.join().unwrap() {
+                assert!(ids.insert(id));
+                assert_eq!(decode(id).2, 1);
+            }
+        }
+        assert_eq!(ids.len(), 80_000);
+    }
+
+    #[test]
+    fn test_atomic_snowflake_counts_the_sequence_in_a_millisecond() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let snowflake = Snowflake::builder(3)
+            .clock(clock.clone())
+            .build_atomic()
+            .unwrap();
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_000, 0, 3));
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_000, 1, 3));
+        clock.advance(Duration::from_millis(5));
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_005, 0, 3));
+
+        // the clock going backward keeps counting from the last millisecond
+        clock.set(Duration::from_millis(900));
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_005, 1, 3));
+    }
+
+    #[test]
+    fn test_atomic_snowflake_waits_out_an_exhausted_sequence() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let layout = SnowflakeLayout::new(44, 2, 10).unwrap();
+        let snowflake = Snowflake::builder(0)
+            .clock(clock.clone())
+            .layout(layout)
+            .build_atomic()
+            .unwrap();
+        for seq in 0..4 {
+            assert_eq!(layout.decode(snowflake.gen().unwrap()), (1_000, seq, 0));
+        }
+        // MockClock sleeps by advancing itself
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (1_001, 0, 0));
+        assert_eq!(clock.now(), Duration::from_millis(1_001));
+    }
+
+    #[test]
+    fn test_atomic_snowflake_carries_on_from_its_builder() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1).clock(clock.clone()).build().unwrap();
+        let last_id = snowflake.gen().unwrap();
+        let atomic = Snowflake::builder(1)
+            .clock(clock.clone())
+            .resume_from(last_id)
+            .build_atomic()
+            .unwrap();
+        assert_eq!(decode(atomic.gen().unwrap()), (1_000, 1, 1));
+
+        let mut snowflake = atomic.into_inner();
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_000, 2, 1));
+    }
+
+    #[test]
+    fn test_atomic_snowflake_reports_timestamp_overflow() {
+        let clock = MockClock::new(Duration::from_millis(1 << 44));
+        let snowflake = Snowflake::builder(1).clock(clock).build_atomic().unwrap();
+        assert!(matches!(
+            snowflake.gen(),
+            Err(SnowflakeError::TimestampOverflowError { .. })
+        ));
+    }
+}
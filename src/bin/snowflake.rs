@@ -0,0 +1,253 @@
+// snowflake gen, decode and convert, for generating and inspecting ids by hand
+
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use snowflake::{DecodedSnowflake, Snowflake, SnowflakeId};
+
+const USAGE: &str = "\
+usage:
+    snowflake gen [-n COUNT] [--service-id ID] [--epoch MILLIS]
+    snowflake decode ID [--epoch MILLIS]
+    snowflake convert [--from decimal|hex|base62] ID
+
+the epoch is in milliseconds since the unix epoch and defaults to it, ids are
+read as decimal unless --from says otherwise, and hex ids may start with 0x";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Decimal,
+    Hex,
+    Base62,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Gen {
+        count: usize,
+        service_id: u16,
+        epoch: SystemTime,
+    },
+    Decode {
+        id: SnowflakeId,
+        epoch: SystemTime,
+    },
+    Convert {
+        id: SnowflakeId,
+    },
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (command, mut rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Err("missing command".to_string()),
+    };
+    let mut count = 1;
+    let mut service_id = 0;
+    let mut epoch = UNIX_EPOCH;
+    let mut format = Format::Decimal;
+    let mut id = None;
+    while let Some((arg, tail)) = rest.split_first() {
+        let mut value = || {
+            let (value, tail) = tail
+                .split_first()
+                .ok_or_else(|| format!("missing value for {arg}"))?;
+            rest = tail;
+            Ok::<_, String>(value.as_str())
+        };
+        match arg.as_str() {
+            "-n" if command == "gen" => count = parse_number(value()?, arg)?,
+            "--service-id" if command == "gen" => service_id = parse_number(value()?, arg)?,
+            "--epoch" if command != "convert" => {
+                epoch = UNIX_EPOCH + Duration::from_millis(parse_number(value()?, arg)?)
+            }
+            "--from" if command == "convert" => {
+                format = match value()? {
+                    "decimal" => Format::Decimal,
+                    "hex" => Format::Hex,
+                    "base62" => Format::Base62,
+                    other => return Err(format!("unknown format {other:?}")),
+                }
+            }
+            _ if command != "gen" && id.is_none() && !arg.starts_with("--") => {
+                id = Some(arg.as_str());
+                rest = tail;
+            }
+            _ => return Err(format!("unexpected argument {arg:?}")),
+        }
+    }
+    let id = |format| {
+        let id = id.ok_or("missing id")?;
+        parse_id(id, format).map_err(|error| error.to_string())
+    };
+    match command {
+        "gen" => Ok(Command::Gen {
+            count,
+            service_id,
+            epoch,
+        }),
+        "decode" => Ok(Command::Decode {
+            id: id(Format::Decimal)?,
+            epoch,
+        }),
+        "convert" => Ok(Command::Convert { id: id(format)? }),
+        other => Err(format!("unknown command {other:?}")),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, arg: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{value:?} is not a valid value for {arg}"))
+}
+
+fn parse_id(id: &str, format: Format) -> Result<SnowflakeId, snowflake::SnowflakeError> {
+    match format {
+        Format::Decimal => id.parse(),
+        Format::Hex => {
+            let digits = id.strip_prefix("0x").unwrap_or(id);
+            let parsed = i64::from_str_radix(digits, 16).map_err(|_| {
+                snowflake::SnowflakeError::InvalidEncodedIdError {
+                    input: id.to_string(),
+                    encoding: "hex",
+                }
+            })?;
+            SnowflakeId::new(parsed)
+        }
+        Format::Base62 => SnowflakeId::from_base62(id),
+    }
+}
+
+fn run(command: Command) -> Result<Vec<String>, snowflake::SnowflakeError> {
+    match command {
+        Command::Gen {
+            count,
+            service_id,
+            epoch,
+        } => {
+            let mut snowflake = Snowflake::builder(service_id).epoch(epoch).build()?;
+            Ok(snowflake
+                .gen_many(count)?
+                .iter()
+                .map(|id| id.to_string())
+                .collect())
+        }
+        Command::Decode { id, epoch } => {
+            let decoded = DecodedSnowflake::new(id.get(), epoch)?;
+            let unix_millis = decoded
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis();
+            Ok(vec![
+                format!("timestamp: {unix_millis} ms since the unix epoch"),
+                format!("sequence: {}", decoded.sequence),
+                format!("service id: {}", decoded.service_id),
+            ])
+        }
+        Command::Convert { id } => Ok(vec![
+            format!("decimal: {id}"),
+            format!("hex: {:#x}", id.get()),
+            format!("base62: {}", id.to_base62()),
+        ]),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match parse_args(&args) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(command) {
+        Ok(lines) => {
+            for line in lines {
+                println!("{line}");
+            }
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snowflake::{decode, pack};
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_gen_makes_count_ids_for_the_service() {
+        let command = parse_args(&args("gen -n 3 --service-id 2 --epoch 1000")).unwrap();
+        assert_eq!(
+            command,
+            Command::Gen {
+                count: 3,
+                service_id: 2,
+                epoch: UNIX_EPOCH + Duration::from_secs(1),
+            }
+        );
+        let ids = run(command).unwrap();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.iter().all(|id| decode(id.parse().unwrap()).2 == 2));
+    }
+
+    #[test]
+    fn test_decode_and_convert_an_id() {
+        let id = pack(1_000, 5, 2);
+        let lines = run(parse_args(&args(&format!("decode {id} --epoch 500"))).unwrap()).unwrap();
+        assert_eq!(
+            lines,
+            [
+                "timestamp: 1500 ms since the unix epoch",
+                "sequence: 5",
+                "service id: 2"
+            ]
+        );
+
+        let hex = format!("{id:#x}");
+        let base62 = SnowflakeId::new(id).unwrap().to_base62();
+        let expected = [
+            format!("decimal: {id}"),
+            format!("hex: {hex}"),
+            format!("base62: {base62}"),
+        ];
+        for input in [
+            id.to_string(),
+            format!("--from hex {hex}"),
+            format!("--from hex {}", &hex[2..]),
+            format!("--from base62 {base62}"),
+        ] {
+            let command = parse_args(&args(&format!("convert {input}"))).unwrap();
+            assert_eq!(run(command).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_bad_arguments_are_rejected() {
+        for bad in [
+            "",
+            "mint",
+            "gen -n",
+            "gen -n many",
+            "gen 5",
+            "decode",
+            "decode -5",
+            "decode 1 2",
+            "convert --from octal 1",
+            "convert --from hex xyz",
+            "convert --epoch 1 1",
+        ] {
+            assert!(parse_args(&args(bad)).is_err(), "{bad:?}");
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, TryRecvError, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::{Clock, Snowflake, SnowflakeError};
+
+/*
+
+a generator that mints ids ahead of time on a background thread and buffers
+them in a bounded channel, so consumers only pay for a channel receive
+
+after shutdown the producer thread is stopped and joined, but ids that were
+already buffered stay retrievable until the channel is drained or dropped
+
+the buffer holds at least one id, as a rendezvous channel would leave the
+producer parked on a full channel with nothing to wake it up
+
+*/
+
+pub struct ChannelSnowflake {
+    receiver: Receiver<i64>,
+    shutdown: Arc<AtomicBool>,
+    producer: Option<JoinHandle<()>>,
+}
+
+impl ChannelSnowflake {
+    pub fn new<C: Clock + Send + 'static>(
+        mut snowflake: Snowflake<C>,
+        buffer: usize,
+    ) -> Result<Self, SnowflakeError> {
+        if buffer == 0 {
+            return Err(SnowflakeError::EmptyBufferError);
+        }
+        let (sender, receiver) = sync_channel(buffer);
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let producer_shutdown = Arc::clone(&shutdown);
+        let producer = thread::spawn(move || {
+            // stop producing once the generator fails, consumers can still drain the buffer
+            let Ok(mut id) = snowflake.gen() else {
+                return;
+            };
+            while !producer_shutdown.load(Ordering::Acquire) {
+                match sender.try_send(id) {
+                    Ok(()) => match snowflake.gen() {
+                        Ok(next) => id = next,
+                        Err(_) => break,
+                    },
+                    // buffer is full, wait until a consumer or shutdown wakes us up
+                    Err(TrySendError::Full(_)) => thread::park(),
+                    // every receiver is gone, nobody is left to consume ids
+                    Err(TrySendError::Disconnected(_)) => break,
+                }
+            }
+        });
+
+        Ok(Self {
+            receiver,
+            shutdown,
+            producer: Some(producer),
+        })
+    }
+
+    // blocks until an id is available, returns None once shut down and drained
+    pub fn recv(&self) -> Option<i64> {
+        let id = self.receiver.recv().ok();
+        self.wake_producer();
+        id
+    }
+
+    // returns None if no id is buffered right now
+    pub fn try_recv(&self) -> Option<i64> {
+        match self.receiver.try_recv() {
+            Ok(id) => {
+                self.wake_producer();
+                Some(id)
+            }
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+
+    // stops and joins the producer, buffered ids can still be received after this
+    pub fn shutdown(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+        if let Some(producer) = self.producer.take() {
+            producer.thread().unpark();
+            // a panic on the producer thread already stopped it, there's nothing left
+            // to shut down
+            let _ = producer.join();
+        }
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        self.producer.is_none()
+    }
+
+    fn wake_producer(&self) {
+        if let Some(producer) = &self.producer {
+            producer.thread().unpark();
+        }
+    }
+}
+
+impl Drop for ChannelSnowflake {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUFFER: usize = 16;
+
+    #[test]
+    fn test_channel_snowflake_creates_unique_positive_ids() {
+        let channel = ChannelSnowflake::new(Snowflake::new(0).unwrap(), BUFFER).unwrap();
+        let mut ids: Vec<i64> = (0..10_000).map(|_| channel.recv().unwrap()).collect();
+        ids.sort();
+        ids.dedup();
+        ids.retain(|id| *id > 0);
+        assert_eq!(ids.len(), 10_000);
+    }
+
+    #[test]
+    fn test_channel_snowflake_drains_buffer_after_shutdown() {
+        let mut channel = ChannelSnowflake::new(Snowflake::new(0).unwrap(), BUFFER).unwrap();
+        let mut ids: Vec<i64> = (0..5).map(|_| channel.recv().unwrap()).collect();
+
+        channel.shutdown();
+        assert!(channel.is_shut_down());
+
+        let mut drained = 0;
+        while let Some(id) = channel.recv() {
+            ids.push(id);
+            drained += 1;
+        }
+        assert!(drained <= BUFFER);
+        assert_eq!(channel.recv(), None);
+        assert_eq!(channel.try_recv(), None);
+
+        let len = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), len);
+    }
+
+    #[test]
+    fn test_channel_snowflake_shutdown_is_idempotent() {
+        let mut channel = ChannelSnowflake::new(Snowflake::new(0).unwrap(), BUFFER).unwrap();
+        channel.shutdown();
+        channel.shutdown();
+        assert!(channel.is_shut_down());
+    }
+
+    #[test]
+    fn test_channel_snowflake_refuses_an_empty_buffer() {
+        assert!(matches!(
+            ChannelSnowflake::new(Snowflake::new(0).unwrap(), 0),
+            Err(SnowflakeError::EmptyBufferError)
+        ));
+    }
+}
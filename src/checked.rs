@@ -0,0 +1,69 @@
+use bloomfilter::Bloom;
+
+use crate::{Clock, Snowflake, SnowflakeError};
+
+/*
+
+a safety net that remembers every id generated in a bloom filter and refuses to
+hand out an id the filter may have seen before, returning a PossibleDuplicateError
+instead, so a bug in the sequence logic shows up as errors instead of collisions
+
+a bloom filter never misses an id it has seen, but also flags ids it hasn't with
+a rate of about false_positive_rate while it holds at most expected_ids, which
+grows as more ids go in, so size it for the ids generated until the process is
+restarted or the generator replaced
+
+the refused id is still consumed, the next gen moves on to the next id
+
+*/
+
+#[derive(Debug)]
+pub struct CheckedSnowflake<C> {
+    snowflake: Snowflake<C>,
+    seen: Bloom<i64>,
+}
+
+impl<C: Clock> CheckedSnowflake<C> {
+    // panics unless expected_ids is positive and false_positive_rate is between 0.0
+    // and 1.0, both exclusive
+    pub fn new(snowflake: Snowflake<C>, expected_ids: usize, false_positive_rate: f64) -> Self {
+        // ids aren't chosen by an adversary, so the hashes need no secret seed
+        let seen = Bloom::new_for_fp_rate_with_seed(expected_ids, false_positive_rate, &[0; 32])
+            .expect("creating a bloom filter never fails");
+        Self { snowflake, seen }
+    }
+
+    pub fn gen(&mut self) -> Result<i64, SnowflakeError> {
+        let id = self.snowflake.gen()?;
+        if self.seen.check_and_set(&id) {
+            return Err(SnowflakeError::PossibleDuplicateError { id });
+        }
+        Ok(id)
+    }
+
+    pub fn into_inner(self) -> Snowflake<C> {
+        self.snowflake
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockClock;
+    use std::time::Duration;
+
+    #[test]
+    fn test_checked_snowflake_flags_a_forced_duplicate() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let snowflake = Snowflake::builder(1).clock(clock).build().unwrap();
+        let mut checked = CheckedSnowflake::new(snowflake, 10_000, 0.000_001);
+        let ids: Vec<i64> = (0..1_000).map(|_| checked.gen().unwrap()).collect();
+
+        // rewind the sequence, as a bug in it would
+        checked.snowflake.seq = 500;
+        assert!(matches!(
+            checked.gen(),
+            Err(SnowflakeError::PossibleDuplicateError { id }) if id == ids[500]
+        ));
+    }
+}
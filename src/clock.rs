@@ -0,0 +1,316 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::thread;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/*
+
+the source of time used by generators
+
+now returns the time elapsed since UNIX_EPOCH, and sleep is used to wait for the
+next millisecond when the sequence is exhausted, so a clock that doesn't follow
+real time can also decide what waiting means
+
+without std it's the only way generators get the time, e.g. a clock over the rtc
+of an embedded board, whose sleep may as well spin or wait for an interrupt, as
+SystemClock only reads the time with std, and the other clocks need it too
+
+*/
+pub trait Clock {
+    fn now(&self) -> Duration;
+    fn sleep(&self, duration: Duration);
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/*
+
+a clock that reads the system time on a background thread every interval and
+caches it as whole milliseconds, so reading it is a single atomic load instead
+of a call into the os
+
+the cached time lags behind the system time by up to a millisecond plus the
+interval, which only matters for how accurate the timestamp of an id is, since
+ids only hold whole milliseconds anyway
+
+the background thread stops once every clone of the clock has been dropped
+
+*/
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct CachedClock {
+    millis: Arc<AtomicU64>,
+}
+
+#[cfg(feature = "std")]
+impl CachedClock {
+    pub fn new() -> Self {
+        Self::with_interval(Duration::from_micros(500))
+    }
+
+    pub fn with_interval(interval: Duration) -> Self {
+        let millis = Arc::new(AtomicU64::new(system_millis()));
+        let weak = Arc::downgrade(&millis);
+        thread::spawn(move || {
+            while let Some(millis) = weak.upgrade() {
+                millis.store(system_millis(), Ordering::Relaxed);
+                drop(millis);
+                thread::sleep(interval);
+            }
+        });
+        Self { millis }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for CachedClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for CachedClock {
+    fn now(&self) -> Duration {
+        Duration::from_millis(self.millis.load(Ordering::Relaxed))
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/*
+
+a clock that reads CLOCK_REALTIME_COARSE on linux, which skips the work of
+interpolating the time since the last timer tick, making it considerably
+cheaper to read at the cost of only being accurate to the tick, usually 1 to 4
+milliseconds, so consecutive milliseconds may be skipped, and ids from the same
+tick share its timestamp
+
+on other platforms it reads the system time like SystemClock
+
+*/
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CoarseClock;
+
+#[cfg(feature = "std")]
+impl Clock for CoarseClock {
+    #[cfg(target_os = "linux")]
+    fn now(&self) -> Duration {
+        let mut time = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // SAFETY: time is a valid timespec to write to, and the clock id is a constant
+        // every supported kernel knows
+        let result = unsafe { libc::clock_gettime(libc::CLOCK_REALTIME_COARSE, &mut time) };
+        if result != 0 {
+            return SystemClock.now();
+        }
+        Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn now(&self) -> Duration {
+        SystemClock.now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+#[cfg(feature = "std")]
+fn system_millis() -> u64 {
+    SystemClock.now().as_millis() as u64
+}
+
+/*
+
+the clock of browsers and edge workers on wasm32-unknown-unknown, where reading
+SystemTime panics, it reads Date.now(), which only has millisecond precision
+
+sleeping returns right away, since blocking would freeze the whole js thread, so
+generators built with Snowflake::wasm_builder give up waiting right away too,
+with a ClockStalledError that is_transient, for the caller to retry on a later
+tick, instead of the sequence running out blocking the page
+
+*/
+#[cfg(feature = "wasm")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WasmClock;
+
+#[cfg(feature = "wasm")]
+impl Clock for WasmClock {
+    fn now(&self) -> Duration {
+        Duration::from_millis(js_sys::Date::now() as u64)
+    }
+
+    fn sleep(&self, _duration: Duration) {}
+}
+
+#[cfg(feature = "wasm")]
+impl crate::Snowflake {
+    pub fn wasm_builder(service_id: u16) -> crate::SnowflakeBuilder<WasmClock> {
+        Self::builder(service_id)
+            .clock(WasmClock)
+            .max_clock_stall(Duration::ZERO)
+    }
+}
+
+/*
+
+a clock that only moves when told to, clones share the same time so a test can
+keep a clone to advance the clock of a generator it handed the original to
+
+sleeping advances the clock by the slept duration, unless the clock is frozen
+
+*/
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    nanos: Arc<AtomicU64>,
+    frozen: Arc<AtomicBool>,
+}
+
+impl MockClock {
+    pub fn new(now: Duration) -> Self {
+        Self {
+            nanos: Arc::new(AtomicU64::new(now.as_nanos() as u64)),
+            frozen: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // a clock set to a point in time instead of the time since UNIX_EPOCH, e.g. the
+    // creation time a test expects ids to carry, times before UNIX_EPOCH clamp to it
+    #[cfg(feature = "std")]
+    pub fn at(time: SystemTime) -> Self {
+        Self::new(time.duration_since(UNIX_EPOCH).unwrap_or_default())
+    }
+
+    // a clock that never advances on its own, not even while sleeping
+    pub fn frozen(now: Duration) -> Self {
+        let clock = Self::new(now);
+        clock.set_frozen(true);
+        clock
+    }
+
+    pub fn set(&self, now: Duration) {
+        self.nanos.store(now.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    pub fn set_frozen(&self, frozen: bool) {
+        self.frozen.store(frozen, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+
+    fn sleep(&self, duration: Duration) {
+        if !self.frozen.load(Ordering::SeqCst) {
+            self.advance(duration);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_cached_clock_follows_system_clock() {
+        let clock = CachedClock::with_interval(Duration::from_micros(100));
+        let before = SystemClock.now();
+        thread::sleep(Duration::from_millis(20));
+        let cached = clock.now();
+        let after = SystemClock.now();
+        assert!(cached > before);
+        assert!(cached <= after);
+        assert_eq!(cached.subsec_micros() % 1_000, 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_cached_clock_stops_updating_once_dropped() {
+        let clock = CachedClock::with_interval(Duration::from_micros(100));
+        let weak = Arc::downgrade(&clock.millis);
+        drop(clock);
+        thread::sleep(Duration::from_millis(5));
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_coarse_clock_follows_system_clock() {
+        let before = SystemClock.now();
+        thread::sleep(Duration::from_millis(20));
+        let coarse = CoarseClock.now();
+        let after = SystemClock.now();
+        assert!(coarse > before);
+        assert!(coarse <= after);
+    }
+
+    #[test]
+    fn test_mock_clock_clones_share_time() {
+        let clock = MockClock::new(Duration::from_millis(5));
+        let clone = clock.clone();
+        clone.advance(Duration::from_millis(2));
+        assert_eq!(clock.now(), Duration::from_millis(7));
+        clock.set(Duration::from_millis(1));
+        assert_eq!(clone.now(), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_mock_clock_sleep_advances_unless_frozen() {
+        let clock = MockClock::new(Duration::ZERO);
+        clock.sleep(Duration::from_micros(300));
+        assert_eq!(clock.now(), Duration::from_micros(300));
+
+        let clock = MockClock::frozen(Duration::ZERO);
+        clock.sleep(Duration::from_micros(300));
+        assert_eq!(clock.now(), Duration::ZERO);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_mock_clock_at_a_point_in_time() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut snowflake = crate::Snowflake::builder(0)
+            .clock(MockClock::at(time))
+            .build()
+            .unwrap();
+        let id = snowflake.gen().unwrap();
+        assert_eq!(crate::timestamp_bits_of(id), 1_700_000_000_000);
+        assert_eq!(
+            MockClock::at(UNIX_EPOCH - Duration::from_secs(1)).now(),
+            Duration::ZERO
+        );
+    }
+}
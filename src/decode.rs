@@ -0,0 +1,189 @@
+#[cfg(feature = "std")]
+use std::io::{self, BufRead};
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "std")]
+use crate::{SnowflakeError, SnowflakeId, SnowflakeLayout};
+
+// the parts of an id, with its timestamp as the time it was generated at
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DecodedSnowflake {
+    pub id: i64,
+    pub timestamp: SystemTime,
+    pub namespace: u8,
+    pub sequence: u32,
+    pub service_id: u16,
+}
+
+#[cfg(feature = "std")]
+impl DecodedSnowflake {
+    // decodes an id of the default layout generated with the given epoch
+    pub fn new(id: i64, epoch: SystemTime) -> Result<Self, SnowflakeError> {
+        Self::with_layout(id, epoch, SnowflakeLayout::DEFAULT)
+    }
+
+    pub fn with_layout(
+        id: i64,
+        epoch: SystemTime,
+        layout: SnowflakeLayout,
+    ) -> Result<Self, SnowflakeError> {
+        let id = SnowflakeId::new(id)?.get();
+        let (millis, sequence, service_id) = layout.decode(id);
+        Ok(Self {
+            id,
+            timestamp: epoch + Duration::from_millis(millis as u64),
+            namespace: layout.namespace_of(id),
+            sequence,
+            service_id,
+        })
+    }
+}
+
+// the raw fields of an id, with the timestamp as the milliseconds since the epoch
+// it was generated with, see Snowflake::parts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SnowflakeParts {
+    pub timestamp_millis: i64,
+    pub seq: u32,
+    pub service_id: u16,
+}
+
+/*
+
+lazily decodes a dump of decimal ids, one per line, e.g. extracted from logs
+
+surrounding whitespace and blank lines are skipped, and every line that can't be
+read or decoded yields its own error, so one bad line doesn't end the stream
+
+*/
+#[cfg(feature = "std")]
+pub fn decode_lines<R: BufRead>(
+    reader: R,
+    epoch: SystemTime,
+) -> impl Iterator<Item = Result<DecodedSnowflake, DecodeLineError>> {
+    reader.lines().enumerate().filter_map(move |(i, line)| {
+        let line_number = i + 1;
+        let line = match line {
+            Ok(line) => line,
+            Err(error) => {
+                return Some(Err(DecodeLineError::IoError {
+                    line: line_number,
+                    error,
+                }))
+            }
+        };
+        let input = line.trim();
+        if input.is_empty() {
+            return None;
+        }
+        let decoded = input
+            .parse()
+            .map_err(|_| SnowflakeError::InvalidIdStringError {
+                input: input.to_string(),
+            })
+            .and_then(|id| DecodedSnowflake::new(id, epoch));
+        Some(decoded.map_err(|error| DecodeLineError::InvalidIdError {
+            line: line_number,
+            error,
+        }))
+    })
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum DecodeLineError {
+    IoError { line: usize, error: io::Error },
+    InvalidIdError { line: usize, error: SnowflakeError },
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for DecodeLineError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::IoError { line, error } => write!(f, "failed to read line {line}: {error}"),
+            Self::InvalidIdError { line, error } => write!(f, "line {line}: {error}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeLineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IoError { error, .. } => Some(error),
+            Self::InvalidIdError { error, .. } => Some(error),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::pack;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_decode_lines_reports_errors_per_line() {
+        let epoch = UNIX_EPOCH + Duration::from_secs(100);
+        let dump = format!(
+            "{}\n\n  {}  \nnot an id\n-5\n{}\n",
+            pack(1_000, 0, 1),
+            pack(1_000, 1, 1),
+            pack(2_500, 7, 3)
+        );
+        let decoded: Vec<_> = decode_lines(dump.as_bytes(), epoch).collect();
+        assert_eq!(decoded.len(), 5);
+
+        assert_eq!(
+            decoded[0].as_ref().unwrap(),
+            &DecodedSnowflake {
+                id: pack(1_000, 0, 1),
+                timestamp: epoch + Duration::from_millis(1_000),
+                namespace: 0,
+                sequence: 0,
+                service_id: 1,
+            }
+        );
+        assert_eq!(decoded[1].as_ref().unwrap().sequence, 1);
+        assert!(matches!(
+            &decoded[2],
+            Err(DecodeLineError::InvalidIdError {
+                line: 4,
+                error: SnowflakeError::InvalidIdStringError { .. },
+            })
+        ));
+        assert!(matches!(
+            &decoded[3],
+            Err(DecodeLineError::InvalidIdError {
+                line: 5,
+                error: SnowflakeError::NegativeIdError { id: -5 },
+            })
+        ));
+        assert_eq!(
+            decoded[4].as_ref().unwrap().timestamp,
+            epoch + Duration::from_millis(2_500)
+        );
+    }
+
+    #[test]
+    fn test_decode_reports_namespace() {
+        let layout = SnowflakeLayout::DEFAULT.with_namespace_bits(2).unwrap();
+        let id = layout.pack_with_namespace(1_000, 3, 5, 1);
+        let decoded = DecodedSnowflake::with_layout(id, UNIX_EPOCH, layout).unwrap();
+        assert_eq!((decoded.namespace, decoded.sequence), (3, 5));
+    }
+
+    #[test]
+    fn test_decode_lines_reports_unreadable_lines() {
+        let dump: &[u8] = b"1\n\xff\xfe\n2\n";
+        let decoded: Vec<_> = decode_lines(dump, UNIX_EPOCH).collect();
+        assert!(decoded[0].is_ok());
+        assert!(matches!(
+            &decoded[1],
+            Err(DecodeLineError::IoError { line: 2, .. })
+        ));
+        assert!(decoded[2].is_ok());
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{Clock, Snowflake, SnowflakeBuilder, SnowflakeError, SnowflakeLayout};
+
+/*
+
+interop with discord snowflakes, which are passed around as decimal strings and
+count milliseconds since the first second of 2015
+
+    timestamp (42 bits) | worker id (5 bits) | process id (5 bits) | increment (12 bits)
+
+generators built with Snowflake::discord_builder mint ids in this format, see
+SnowflakeLayout::DISCORD for how the worker and process ids map to a service id
+
+*/
+
+pub const DISCORD_EPOCH: Duration = Duration::from_millis(1_420_070_400_000);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscordId {
+    pub timestamp: SystemTime,
+    pub worker_id: u8,
+    pub process_id: u8,
+    pub increment: u16,
+}
+
+// parses and decodes the decimal text of any discord id, including ones from
+// discord itself
+pub fn decode_discord(id: &str) -> Result<DiscordId, SnowflakeError> {
+    let parsed: u64 = id
+        .parse()
+        .map_err(|_| SnowflakeError::InvalidIdStringError {
+            input: id.to_string(),
+        })?;
+    Ok(DiscordId {
+        timestamp: UNIX_EPOCH + DISCORD_EPOCH + Duration::from_millis(parsed >> 22),
+        worker_id: (parsed >> 17 & 0x1f) as u8,
+        process_id: (parsed >> 12 & 0x1f) as u8,
+        increment: (parsed & 0xfff) as u16,
+    })
+}
+
+impl Snowflake {
+    // a builder with the discord layout and epoch, service_id is worker << 5 | process
+    pub fn discord_builder(service_id: u16) -> SnowflakeBuilder {
+        Self::builder(service_id)
+            .layout(SnowflakeLayout::DISCORD)
+            .epoch(UNIX_EPOCH + DISCORD_EPOCH)
+    }
+}
+
+impl<C: Clock> Snowflake<C> {
+    // generates an id as the decimal text discord apis expect, meant for generators
+    // built with discord_builder
+    pub fn gen_discord_string(&mut self) -> Result<String, SnowflakeError> {
+        Ok(self.gen()?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockClock;
+
+    #[test]
+    fn test_decode_discord_id() {
+        // the example id from the discord api reference
+        let id = decode_discord("175928847299117063").unwrap();
+        assert_eq!(
+            id,
+            DiscordId {
+                timestamp: UNIX_EPOCH + Duration::from_millis(1_462_015_105_796),
+                worker_id: 1,
+                process_id: 0,
+                increment: 7,
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_discord_rejects_malformed_ids() {
+        for id in ["", "-1", "12a", "18446744073709551616"] {
+            assert!(matches!(
+                decode_discord(id),
+                Err(SnowflakeError::InvalidIdStringError { input }) if input == id
+            ));
+        }
+    }
+
+    #[test]
+    fn test_gen_discord_string_round_trips() {
+        let now = DISCORD_EPOCH + Duration::from_millis(41_944_705_796);
+        let mut snowflake = Snowflake::discord_builder(1 << 5 | 3)
+            .clock(MockClock::new(now))
+            .build()
+            .unwrap();
+        snowflake.gen().unwrap();
+        let id = snowflake.gen_discord_string().unwrap();
+        assert_eq!(
+            decode_discord(&id).unwrap(),
+            DiscordId {
+                timestamp: UNIX_EPOCH + now,
+                worker_id: 1,
+                process_id: 3,
+                increment: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{SnowflakeError, SnowflakeId};
+
+/*
+
+ids as short strings for urls and anything else copied around by hand, in
+base62 for the shortest, base58 for one without the lookalikes 0, O, I and l,
+and crockford's base32 for one that is case-insensitive and also survives being
+read aloud
+
+digits go from most to least significant without padding, so strings of the
+same length sort in id order, but a shorter string isn't always a smaller id,
+pad them to the longest length of their encoding (11, 11 and 13) to sort them
+
+base32 parsing ignores case and dashes and reads I and L as 1 and O as 0, the
+others take exactly their alphabet, and every encoding rejects empty strings
+and values beyond i64::MAX
+
+*/
+
+const BASE62: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const BASE58: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE32: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+fn encode(id: SnowflakeId, alphabet: &[u8]) -> String {
+    let radix = alphabet.len() as u64;
+    let mut n = id.get() as u64;
+    let mut digits = Vec::new();
+    loop {
+        digits.push(alphabet[(n % radix) as usize]);
+        n /= radix;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    String::from_utf8(digits).expect("alphabets are ascii")
+}
+
+fn decode(
+    input: &str,
+    encoding: &'static str,
+    radix: u64,
+    digits: impl Iterator<Item = Option<u64>>,
+) -> Result<SnowflakeId, SnowflakeError> {
+    let error = || SnowflakeError::InvalidEncodedIdError {
+        input: input.to_string(),
+        encoding,
+    };
+    let mut n: u64 = 0;
+    let mut empty = true;
+    for digit in digits {
+        n = n
+            .checked_mul(radix)
+            .and_then(|n| n.checked_add(digit?))
+            .ok_or_else(error)?;
+        empty = false;
+    }
+    if empty || n > i64::MAX as u64 {
+        return Err(error());
+    }
+    SnowflakeId::new(n as i64)
+}
+
+fn position(alphabet: &[u8], c: u8) -> Option<u64> {
+    alphabet.iter().position(|&d| d == c).map(|i| i as u64)
+}
+
+impl SnowflakeId {
+    // the decimal id zero padded to the 19 digits of i64::MAX, which no layout can
+    // exceed, so padded strings sort in id order, e.g. in string typed columns
+    pub fn to_padded_string(self) -> String {
+        format!("{:019}", self.get())
+    }
+
+    pub fn to_base62(self) -> String {
+        encode(self, BASE62)
+    }
+
+    pub fn from_base62(input: &str) -> Result<Self, SnowflakeError> {
+        let digits = input.bytes().map(|c| position(BASE62, c));
+        decode(input, "base62", 62, digits)
+    }
+
+    pub fn to_base58(self) -> String {
+        encode(self, BASE58)
+    }
+
+    pub fn from_base58(input: &str) -> Result<Self, SnowflakeError> {
+        let digits = input.bytes().map(|c| position(BASE58, c));
+        decode(input, "base58", 58, digits)
+    }
+
+    // crockford's base32, in uppercase
+    pub fn to_base32(self) -> String {
+        encode(self, BASE32)
+    }
+
+    pub fn from_base32(input: &str) -> Result<Self, SnowflakeError> {
+        let digits = input
+            .bytes()
+            .filter(|&c| c != b'-')
+            .map(|c| match c.to_ascii_uppercase() {
+                b'I' | b'L' => Some(1),
+                b'O' => Some(0),
+                c => position(BASE32, c),
+            });
+        decode(input, "base32", 32, digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack;
+
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_every_encoding_round_trips(id in 0..=i64::MAX) {
+            let id = SnowflakeId::new(id).unwrap();
+            prop_assert_eq!(SnowflakeId::from_base62(&id.to_base62()).unwrap(), id);
+            prop_assert_eq!(SnowflakeId::from_base58(&id.to_base58()).unwrap(), id);
+            prop_assert_eq!(SnowflakeId::from_base32(&id.to_base32()).unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn test_encodings_of_known_ids() {
+        let zero = SnowflakeId::new(0).unwrap();
+        assert_eq!(
+            (zero.to_base62(), zero.to_base58(), zero.to_base32()),
+            ("0".to_string(), "1".to_string(), "0".to_string())
+        );
+        let max = SnowflakeId::new(i64::MAX).unwrap();
+        assert_eq!(max.to_base62(), "AzL8n0Y58m7");
+        assert_eq!(max.to_base58(), "NQm6nKp8qFC");
+        assert_eq!(max.to_base32(), "7ZZZZZZZZZZZZ");
+    }
+
+    #[test]
+    fn test_base32_is_lenient_like_crockford_says() {
+        let id = SnowflakeId::new(pack(1_000, 5, 2)).unwrap();
+        let encoded = id.to_base32();
+        let lowercase = encoded.to_ascii_lowercase();
+        assert_eq!(SnowflakeId::from_base32(&lowercase).unwrap(), id);
+        let dashed = format!("{}-{}", &encoded[..4], &encoded[4..]);
+        assert_eq!(SnowflakeId::from_base32(&dashed).unwrap(), id);
+        assert_eq!(
+            SnowflakeId::from_base32("IlO").unwrap(),
+            SnowflakeId::from_base32("110").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_malformed_input_is_rejected() {
+        type Decode = fn(&str) -> Result<SnowflakeId, SnowflakeError>;
+        let cases: [(&str, Decode, &str); 9] = [
+            ("", SnowflakeId::from_base62, "base62"),
+            ("ab-c", SnowflakeId::from_base62, "base62"),
+            // one beyond i64::MAX
+            ("AzL8n0Y58m8", SnowflakeId::from_base62, "base62"),
+            ("0", SnowflakeId::from_base58, "base58"),
+            ("Il", SnowflakeId::from_base58, "base58"),
+            ("NQm6nKp8qFD", SnowflakeId::from_base58, "base58"),
+            ("-", SnowflakeId::from_base32, "base32"),
+            ("U", SnowflakeId::from_base32, "base32"),
+            ("80000000000000", SnowflakeId::from_base32, "base32"),
+        ];
+        for (input, from, name) in cases {
+            assert!(
+                matches!(
+                    from(input),
+                    Err(SnowflakeError::InvalidEncodedIdError { input: found, encoding })
+                        if found == input && encoding == name
+                ),
+                "{input}"
+            );
+        }
+    }
+}
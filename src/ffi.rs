@@ -0,0 +1,182 @@
+// the safety contract of every function is in the comment below
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::{c_char, CStr};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::{AtomicSnowflake, Snowflake, SnowflakeError};
+
+/*
+
+a c abi for generating ids from other languages, so a c or c++ service shares
+the id space of the rust ones, build it as a shared library with
+
+    cargo rustc --release --features ffi --crate-type cdylib
+
+and generate a header with cbindgen, every type here is repr(C) or opaque
+
+    SnowflakeGenerator *generator;
+    if (snowflake_new(1, 0, &generator) != SNOWFLAKE_STATUS_OK) { ... }
+    int64_t id;
+    SnowflakeStatus status = snowflake_gen(generator, &id);
+    snowflake_free(generator);
+
+a generator is an AtomicSnowflake, so one pointer can be shared by any number of
+threads, every pointer passed in must be null or come from snowflake_new and
+not be freed yet, out pointers must be valid for writes, and after
+snowflake_free the generator must not be used again
+
+*/
+
+// opaque to c, only ever handled through a pointer
+pub struct SnowflakeGenerator(AtomicSnowflake);
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnowflakeStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidServiceId = 2,
+    TimestampOverflow = 3,
+    // the clock is before the epoch, stalled or went back further than allowed
+    Clock = 4,
+    Other = 5,
+}
+
+impl From<&SnowflakeError> for SnowflakeStatus {
+    fn from(error: &SnowflakeError) -> Self {
+        match error {
+            SnowflakeError::InvalidServiceIdError { .. } => Self::InvalidServiceId,
+            SnowflakeError::TimestampOverflowError { .. } => Self::TimestampOverflow,
+            SnowflakeError::ClockBeforeEpochError { .. }
+            | SnowflakeError::ClockStalledError { .. }
+            | SnowflakeError::ClockWentBackwardError { .. } => Self::Clock,
+            _ => Self::Other,
+        }
+    }
+}
+
+// a generator with the default layout and an epoch in milliseconds since the unix
+// epoch, written to out, which is left untouched on errors
+#[no_mangle]
+pub unsafe extern "C" fn snowflake_new(
+    service_id: u16,
+    epoch_millis: u64,
+    out: *mut *mut SnowflakeGenerator,
+) -> SnowflakeStatus {
+    if out.is_null() {
+        return SnowflakeStatus::NullPointer;
+    }
+    let epoch = UNIX_EPOCH + Duration::from_millis(epoch_millis);
+    match Snowflake::builder(service_id).epoch(epoch).build_atomic() {
+        Ok(snowflake) => {
+            let generator = Box::new(SnowflakeGenerator(snowflake));
+            // SAFETY: out is non-null and valid for writes as the caller promised
+            unsafe { out.write(Box::into_raw(generator)) };
+            SnowflakeStatus::Ok
+        }
+        Err(error) => (&error).into(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn snowflake_gen(
+    generator: *const SnowflakeGenerator,
+    out: *mut i64,
+) -> SnowflakeStatus {
+    if generator.is_null() || out.is_null() {
+        return SnowflakeStatus::NullPointer;
+    }
+    // SAFETY: generator is non-null and came from snowflake_new, it is only ever
+    // shared, never mutated, so any number of threads may hold it
+    let generator = unsafe { &*generator };
+    match generator.0.gen() {
+        Ok(id) => {
+            // SAFETY: out is non-null and valid for writes as the caller promised
+            unsafe { out.write(id) };
+            SnowflakeStatus::Ok
+        }
+        Err(error) => (&error).into(),
+    }
+}
+
+// null is ignored, like free does
+#[no_mangle]
+pub unsafe extern "C" fn snowflake_free(generator: *mut SnowflakeGenerator) {
+    if !generator.is_null() {
+        // SAFETY: generator came from Box::into_raw in snowflake_new and isn't used
+        // after this
+        drop(unsafe { Box::from_raw(generator) });
+    }
+}
+
+// a static, nul terminated description of a status, never to be freed
+#[no_mangle]
+pub extern "C" fn snowflake_status_message(status: SnowflakeStatus) -> *const c_char {
+    let message: &'static CStr = match status {
+        SnowflakeStatus::Ok => c"ok",
+        SnowflakeStatus::NullPointer => c"a required pointer was null",
+        SnowflakeStatus::InvalidServiceId => c"the service id doesn't fit the layout",
+        SnowflakeStatus::TimestampOverflow => c"the timestamp overflowed the layout",
+        SnowflakeStatus::Clock => c"the clock is before the epoch, stalled or went backward",
+        SnowflakeStatus::Other => c"generating failed",
+    };
+    message.as_ptr()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode;
+    use std::ptr;
+
+    #[test]
+    fn test_generators_round_trip_through_the_c_abi() {
+        let mut generator = ptr::null_mut();
+        assert_eq!(
+            unsafe { snowflake_new(3, 0, &mut generator) },
+            SnowflakeStatus::Ok
+        );
+        let mut ids = [0; 2];
+        for id in &mut ids {
+            assert_eq!(unsafe { snowflake_gen(generator, id) }, SnowflakeStatus::Ok);
+        }
+        assert!(ids[0] < ids[1]);
+        assert_eq!(decode(ids[0]).2, 3);
+        unsafe { snowflake_free(generator) };
+    }
+
+    #[test]
+    fn test_errors_come_back_as_statuses() {
+        let mut generator = ptr::null_mut();
+        assert_eq!(
+            unsafe { snowflake_new(4, 0, &mut generator) },
+            SnowflakeStatus::InvalidServiceId
+        );
+        assert!(generator.is_null());
+        assert_eq!(
+            unsafe { snowflake_new(0, 0, ptr::null_mut()) },
+            SnowflakeStatus::NullPointer
+        );
+        let mut id = 0;
+        assert_eq!(
+            unsafe { snowflake_gen(ptr::null(), &mut id) },
+            SnowflakeStatus::NullPointer
+        );
+        unsafe { snowflake_free(ptr::null_mut()) };
+
+        // an epoch far in the future puts the clock before it
+        assert_eq!(
+            unsafe { snowflake_new(0, u64::MAX / 2, &mut generator) },
+            SnowflakeStatus::Ok
+        );
+        assert_eq!(
+            unsafe { snowflake_gen(generator, &mut id) },
+            SnowflakeStatus::Clock
+        );
+        unsafe { snowflake_free(generator) };
+
+        let message = unsafe { CStr::from_ptr(snowflake_status_message(SnowflakeStatus::Clock)) };
+        assert!(message.to_str().unwrap().starts_with("the clock"));
+    }
+}
@@ -0,0 +1,490 @@
+use alloc::string::ToString;
+
+use crate::{sequence_of, service_id_of, timestamp_bits_of, SnowflakeError, SnowflakeLayout};
+
+// an id produced by a generator, always positive since the signing bit is never set
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "diesel",
+    derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow),
+    diesel(sql_type = diesel::sql_types::BigInt)
+)]
+pub struct SnowflakeId(i64);
+
+impl SnowflakeId {
+    pub fn new(id: i64) -> Result<Self, SnowflakeError> {
+        if id < 0 {
+            return Err(SnowflakeError::NegativeIdError { id });
+        }
+        Ok(Self(id))
+    }
+
+    pub const fn get(self) -> i64 {
+        self.0
+    }
+
+    // the fields of the id in the default layout, see into_parts for other layouts
+    pub const fn timestamp_bits(self) -> i64 {
+        timestamp_bits_of(self.0)
+    }
+
+    pub const fn sequence(self) -> u32 {
+        sequence_of(self.0)
+    }
+
+    pub const fn service_id(self) -> u16 {
+        service_id_of(self.0)
+    }
+
+    // rejects ids of the default layout whose service id isn't allowed, e.g. at a
+    // service boundary to refuse ids minted by other services
+    pub fn require_service_in(&self, allowed: &[u16]) -> Result<(), SnowflakeError> {
+        let service_id = service_id_of(self.0);
+        if !allowed.contains(&service_id) {
+            return Err(SnowflakeError::UnexpectedServiceIdError {
+                service_id,
+                allowed: allowed.to_vec(),
+            });
+        }
+        Ok(())
+    }
+
+    // (timestamp, sequence, service id) of the id in the given layout
+    pub const fn into_parts(self, layout: &SnowflakeLayout) -> (i64, u32, u16) {
+        layout.decode(self.0)
+    }
+
+    // the inverse of into_parts, every part must fit its width in the layout
+    pub fn from_parts(
+        layout: &SnowflakeLayout,
+        millis: i64,
+        seq: u32,
+        service_id: u16,
+    ) -> Result<Self, SnowflakeError> {
+        if !(0..=layout.max_timestamp()).contains(&millis) {
+            return Err(SnowflakeError::InvalidTimestampError {
+                millis,
+                max: layout.max_timestamp(),
+            });
+        }
+        if seq > layout.max_sequence() {
+            return Err(SnowflakeError::InvalidSequenceError {
+                seq,
+                max: layout.max_sequence(),
+            });
+        }
+        if service_id > layout.max_service_id() {
+            return Err(SnowflakeError::InvalidServiceIdError {
+                service_id,
+                max: layout.max_service_id(),
+            });
+        }
+        Ok(Self(layout.pack(millis, seq, service_id)))
+    }
+
+    // big-endian bytes compare lexicographically in the same order as the ids
+    // themselves, so they are safe to use as sortable keys
+    pub const fn to_be_bytes(self) -> [u8; 8] {
+        self.0.to_be_bytes()
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 8]) -> Result<Self, SnowflakeError> {
+        Self::new(i64::from_be_bytes(bytes))
+    }
+
+    // little-endian bytes do NOT sort lexicographically in id order, only use them
+    // to interoperate with systems that expect little-endian, never as sortable keys
+    pub const fn to_le_bytes(self) -> [u8; 8] {
+        self.0.to_le_bytes()
+    }
+
+    // see to_le_bytes, the result of sorting little-endian bytes is not id order
+    pub fn from_le_bytes(bytes: [u8; 8]) -> Result<Self, SnowflakeError> {
+        Self::new(i64::from_le_bytes(bytes))
+    }
+}
+
+// decoded with the default layout, ts is the timestamp field as an id doesn't know
+// its epoch
+impl core::fmt::Debug for SnowflakeId {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("SnowflakeId")
+            .field("ts", &timestamp_bits_of(self.0))
+            .field("seq", &sequence_of(self.0))
+            .field("service", &service_id_of(self.0))
+            .finish()
+    }
+}
+
+impl core::fmt::Display for SnowflakeId {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<i64> for SnowflakeId {
+    type Error = SnowflakeError;
+
+    fn try_from(id: i64) -> Result<Self, Self::Error> {
+        Self::new(id)
+    }
+}
+
+impl From<SnowflakeId> for i64 {
+    fn from(id: SnowflakeId) -> Self {
+        id.0
+    }
+}
+
+// u64s beyond i64::MAX are the ones that would set the signing bit, so they are
+// rejected as the NegativeIdError they are as an i64
+impl TryFrom<u64> for SnowflakeId {
+    type Error = SnowflakeError;
+
+    fn try_from(id: u64) -> Result<Self, Self::Error> {
+        Self::new(id as i64)
+    }
+}
+
+impl From<SnowflakeId> for u64 {
+    fn from(id: SnowflakeId) -> Self {
+        id.0 as u64
+    }
+}
+
+// rejects negative ids like SnowflakeId::new
+impl core::str::FromStr for SnowflakeId {
+    type Err = SnowflakeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let id = s
+            .parse()
+            .map_err(|_| SnowflakeError::InvalidIdStringError {
+                input: s.to_string(),
+            })?;
+        Self::new(id)
+    }
+}
+
+// a plain number, see serde_string for ids read by javascript, which can't hold
+// integers beyond 2^53 without losing precision
+#[cfg(feature = "serde")]
+impl serde::Serialize for SnowflakeId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SnowflakeId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = i64::deserialize(deserializer)?;
+        Self::new(id).map_err(serde::de::Error::custom)
+    }
+}
+
+/*
+
+stored like an i64, e.g. in a postgres BIGINT column, with any database sqlx or
+diesel supports bigints on, and like deserializing, reading a negative id from
+the database is an error instead of a SnowflakeId that SnowflakeId::new rejects
+
+*/
+
+#[cfg(feature = "sqlx")]
+impl<DB: sqlx::Database> sqlx::Type<DB> for SnowflakeId
+where
+    i64: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <i64 as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <i64 as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<'q, DB: sqlx::Database> sqlx::Encode<'q, DB> for SnowflakeId
+where
+    i64: sqlx::Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut DB::ArgumentBuffer,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        self.0.encode_by_ref(buf)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for SnowflakeId
+where
+    i64: sqlx::Decode<'r, DB>,
+{
+    fn decode(value: DB::ValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(Self::new(i64::decode(value)?)?)
+    }
+}
+
+#[cfg(feature = "diesel")]
+impl<DB: diesel::backend::Backend> diesel::serialize::ToSql<diesel::sql_types::BigInt, DB>
+    for SnowflakeId
+where
+    i64: diesel::serialize::ToSql<diesel::sql_types::BigInt, DB>,
+{
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, DB>,
+    ) -> diesel::serialize::Result {
+        self.0.to_sql(out)
+    }
+}
+
+#[cfg(feature = "diesel")]
+impl<DB: diesel::backend::Backend> diesel::deserialize::FromSql<diesel::sql_types::BigInt, DB>
+    for SnowflakeId
+where
+    i64: diesel::deserialize::FromSql<diesel::sql_types::BigInt, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> diesel::deserialize::Result<Self> {
+        Ok(Self::new(i64::from_sql(bytes)?)?)
+    }
+}
+
+// only ever yields ids that SnowflakeId::new accepts, for fuzzing code that takes ids
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for SnowflakeId {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self(u.int_in_range(0..=i64::MAX)?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        i64::size_hint(depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack;
+    #[cfg(feature = "std")]
+    use crate::Snowflake;
+
+    #[test]
+    fn test_debug_shows_decoded_fields_and_display_the_number() {
+        let id = SnowflakeId::new(pack(1_000, 5, 2)).unwrap();
+        assert_eq!(
+            format!("{id:?}"),
+            "SnowflakeId { ts: 1000, seq: 5, service: 2 }"
+        );
+        assert_eq!(id.to_string(), pack(1_000, 5, 2).to_string());
+    }
+
+    #[test]
+    fn test_require_service_in_rejects_other_services() {
+        let id = SnowflakeId::new(pack(1_000, 5, 2)).unwrap();
+        assert!(id.require_service_in(&[1, 2]).is_ok());
+        assert!(matches!(
+            id.require_service_in(&[0, 3]),
+            Err(SnowflakeError::UnexpectedServiceIdError { service_id: 2, allowed })
+                if allowed == [0, 3]
+        ));
+        assert!(id.require_service_in(&[]).is_err());
+    }
+
+    #[test]
+    fn test_parts_round_trip_in_any_layout() {
+        for layout in [SnowflakeLayout::DEFAULT, SnowflakeLayout::DISCORD] {
+            let id = SnowflakeId::from_parts(&layout, 123_456, 78, 3).unwrap();
+            assert_eq!(id.into_parts(&layout), (123_456, 78, 3));
+        }
+    }
+
+    #[test]
+    fn test_from_parts_rejects_parts_that_dont_fit() {
+        let layout = SnowflakeLayout::new(10, 4, 2).unwrap();
+        assert!(matches!(
+            SnowflakeId::from_parts(&layout, 1_024, 0, 0),
+            Err(SnowflakeError::InvalidTimestampError {
+                millis: 1_024,
+                max: 1_023
+            })
+        ));
+        assert!(matches!(
+            SnowflakeId::from_parts(&layout, -1, 0, 0),
+            Err(SnowflakeError::InvalidTimestampError { millis: -1, .. })
+        ));
+        assert!(matches!(
+            SnowflakeId::from_parts(&layout, 0, 16, 0),
+            Err(SnowflakeError::InvalidSequenceError { seq: 16, max: 15 })
+        ));
+        assert!(matches!(
+            SnowflakeId::from_parts(&layout, 0, 0, 4),
+            Err(SnowflakeError::InvalidServiceIdError {
+                service_id: 4,
+                max: 3
+            })
+        ));
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_ids_are_valid() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let bytes: Vec<u8> = (0..=255).cycle().take(4_096).collect();
+        let mut u = Unstructured::new(&bytes);
+        while !u.is_empty() {
+            let id = SnowflakeId::arbitrary(&mut u).unwrap();
+            assert_eq!(SnowflakeId::new(id.get()).unwrap(), id);
+        }
+        let max = [0xff; 8];
+        let id = SnowflakeId::arbitrary(&mut Unstructured::new(&max)).unwrap();
+        assert!(id.get() >= 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_bytes_round_trip_in_both_endiannesses() {
+        let id = SnowflakeId::new(Snowflake::new(1).unwrap().gen().unwrap()).unwrap();
+        assert_eq!(SnowflakeId::from_be_bytes(id.to_be_bytes()).unwrap(), id);
+        assert_eq!(SnowflakeId::from_le_bytes(id.to_le_bytes()).unwrap(), id);
+        assert_ne!(id.to_be_bytes(), id.to_le_bytes());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_big_endian_bytes_sort_in_id_order() {
+        let mut snowflake = Snowflake::new(0).unwrap();
+        let ids: Vec<SnowflakeId> = (0..1_000)
+            .map(|_| SnowflakeId::new(snowflake.gen().unwrap()).unwrap())
+            .collect();
+        let mut bytes: Vec<[u8; 8]> = ids.iter().rev().map(|id| id.to_be_bytes()).collect();
+        bytes.sort();
+        let sorted: Vec<SnowflakeId> = bytes
+            .into_iter()
+            .map(|bytes| SnowflakeId::from_be_bytes(bytes).unwrap())
+            .collect();
+        assert_eq!(sorted, ids);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_padded_strings_sort_in_id_order() {
+        let mut ids: Vec<SnowflakeId> = [0, 7, 10, 99, pack(1, 0, 0), pack(1_000, 5, 2), i64::MAX]
+            .into_iter()
+            .map(|id| SnowflakeId::new(id).unwrap())
+            .collect();
+        let mut snowflake = Snowflake::new(0).unwrap();
+        ids.extend((0..100).map(|_| SnowflakeId::new(snowflake.gen().unwrap()).unwrap()));
+
+        let mut padded: Vec<String> = ids.iter().rev().map(|id| id.to_padded_string()).collect();
+        assert!(padded.iter().all(|padded| padded.len() == 19));
+        padded.sort();
+        let sorted: Vec<SnowflakeId> = padded
+            .iter()
+            .map(|padded| SnowflakeId::new(padded.parse().unwrap()).unwrap())
+            .collect();
+        ids.sort();
+        assert_eq!(sorted, ids);
+        assert_eq!(
+            SnowflakeId::new(42).unwrap().to_padded_string(),
+            "0000000000000000042"
+        );
+    }
+
+    #[test]
+    fn test_conversions_and_accessors() {
+        let id = SnowflakeId::try_from(pack(1_000, 5, 2)).unwrap();
+        assert_eq!(i64::from(id), pack(1_000, 5, 2));
+        assert_eq!(
+            (id.timestamp_bits(), id.sequence(), id.service_id()),
+            (1_000, 5, 2)
+        );
+        assert!(matches!(
+            SnowflakeId::try_from(-1i64),
+            Err(SnowflakeError::NegativeIdError { id: -1 })
+        ));
+
+        let unsigned = pack(1_000, 5, 2) as u64;
+        assert_eq!(SnowflakeId::try_from(unsigned).unwrap(), id);
+        assert_eq!(u64::from(id), unsigned);
+        assert!(matches!(
+            SnowflakeId::try_from(u64::MAX),
+            Err(SnowflakeError::NegativeIdError { id: -1 })
+        ));
+        assert!(SnowflakeId::try_from(i64::MAX as u64).is_ok());
+    }
+
+    #[cfg(feature = "sqlx")]
+    #[test]
+    fn test_sqlx_stores_ids_as_bigints() {
+        use sqlx::postgres::{PgArgumentBuffer, Postgres};
+        use sqlx::{Encode, Type};
+
+        assert_eq!(
+            <SnowflakeId as Type<Postgres>>::type_info(),
+            <i64 as Type<Postgres>>::type_info()
+        );
+        let id = SnowflakeId::new(pack(1_000, 5, 2)).unwrap();
+        let mut encoded = PgArgumentBuffer::default();
+        let _ = Encode::<Postgres>::encode_by_ref(&id, &mut encoded).unwrap();
+        let mut expected = PgArgumentBuffer::default();
+        let _ = Encode::<Postgres>::encode_by_ref(&id.get(), &mut expected).unwrap();
+        assert_eq!(*encoded, *expected);
+    }
+
+    #[cfg(feature = "diesel")]
+    #[test]
+    fn test_diesel_binds_ids_as_bigints() {
+        use diesel::pg::Pg;
+        use diesel::sql_types::BigInt;
+        use diesel::IntoSql;
+
+        let id = SnowflakeId::new(pack(1_000, 5, 2)).unwrap();
+        let query = diesel::select(id.into_sql::<BigInt>());
+        assert_eq!(
+            diesel::debug_query::<Pg, _>(&query).to_string(),
+            format!("SELECT $1 -- binds: [{id:?}]")
+        );
+    }
+
+    #[test]
+    fn test_from_str_rejects_what_isnt_an_id() {
+        let id: SnowflakeId = pack(1_000, 5, 2).to_string().parse().unwrap();
+        assert_eq!(id.get(), pack(1_000, 5, 2));
+        assert!(matches!(
+            "-5".parse::<SnowflakeId>(),
+            Err(SnowflakeError::NegativeIdError { id: -5 })
+        ));
+        assert!(matches!(
+            " 5".parse::<SnowflakeId>(),
+            Err(SnowflakeError::InvalidIdStringError { input }) if input == " 5"
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_uses_a_number_and_rejects_negative_ids() {
+        let id = SnowflakeId::new(pack(1_000, 5, 2)).unwrap();
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, pack(1_000, 5, 2).to_string());
+        assert_eq!(serde_json::from_str::<SnowflakeId>(&json).unwrap(), id);
+        assert!(serde_json::from_str::<SnowflakeId>("-5").is_err());
+    }
+
+    #[test]
+    fn test_bytes_with_signing_bit_are_rejected() {
+        let bytes = (-1i64).to_be_bytes();
+        assert!(matches!(
+            SnowflakeId::from_be_bytes(bytes),
+            Err(SnowflakeError::NegativeIdError { id: -1 })
+        ));
+        assert!(matches!(
+            SnowflakeId::from_le_bytes(bytes),
+            Err(SnowflakeError::NegativeIdError { id: -1 })
+        ));
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{Clock, Snowflake, SnowflakeBuilder, SnowflakeError, SnowflakeLayout};
+
+/*
+
+interop with instagram style sharded ids, which count milliseconds since the
+epoch instagram picked for its own ids
+
+    timestamp (41 bits) | shard id (13 bits) | sequence (10 bits)
+
+generators built with Snowflake::instagram_builder mint ids in this format, with
+the shard id as their service id
+
+*/
+
+pub const INSTAGRAM_EPOCH: Duration = Duration::from_millis(1_314_220_021_721);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstagramId {
+    pub timestamp: SystemTime,
+    pub shard_id: u16,
+    pub sequence: u32,
+}
+
+pub fn decode_instagram(id: i64) -> Result<InstagramId, SnowflakeError> {
+    if id < 0 {
+        return Err(SnowflakeError::NegativeIdError { id });
+    }
+    let (millis, sequence, shard_id) = SnowflakeLayout::INSTAGRAM.decode(id);
+    Ok(InstagramId {
+        timestamp: UNIX_EPOCH + INSTAGRAM_EPOCH + Duration::from_millis(millis as u64),
+        shard_id,
+        sequence,
+    })
+}
+
+impl Snowflake {
+    // a builder with the instagram layout and epoch
+    pub fn instagram_builder(shard_id: u16) -> SnowflakeBuilder {
+        Self::builder(shard_id)
+            .layout(SnowflakeLayout::INSTAGRAM)
+            .epoch(UNIX_EPOCH + INSTAGRAM_EPOCH)
+    }
+}
+
+impl<C: Clock> Snowflake<C> {
+    pub fn shard_id_of(&self, id: i64) -> u16 {
+        self.layout.service_id_of(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockClock;
+
+    // the example from instagram's post on their id scheme, 1387263000ms after the
+    // epoch, on shard 1341 with sequence 5001 % 1024
+    const EXAMPLE_ID: i64 = 11_637_205_501_278_089;
+
+    #[test]
+    fn test_decode_instagram_id() {
+        assert_eq!(
+            decode_instagram(EXAMPLE_ID).unwrap(),
+            InstagramId {
+                timestamp: UNIX_EPOCH + INSTAGRAM_EPOCH + Duration::from_millis(1_387_263_000),
+                shard_id: 1_341,
+                sequence: 905,
+            }
+        );
+        assert!(matches!(
+            decode_instagram(-1),
+            Err(SnowflakeError::NegativeIdError { id: -1 })
+        ));
+    }
+
+    #[test]
+    fn test_instagram_builder_generates_instagram_ids() {
+        let now = INSTAGRAM_EPOCH + Duration::from_millis(1_387_263_000);
+        let mut snowflake = Snowflake::instagram_builder(1_341)
+            .clock(MockClock::new(now))
+            .build()
+            .unwrap();
+        for _ in 0..905 {
+            snowflake.gen().unwrap();
+        }
+        let id = snowflake.gen().unwrap();
+        assert_eq!(id, EXAMPLE_ID);
+        assert_eq!(snowflake.shard_id_of(id), 1_341);
+        assert!(Snowflake::instagram_builder(8_192).build().is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{Clock, Snowflake, SnowflakeError};
+
+// an endless stream of gen results, see Snowflake::ids
+#[derive(Debug)]
+pub struct Ids<'a, C> {
+    snowflake: &'a mut Snowflake<C>,
+}
+
+impl<C: Clock> Iterator for Ids<'_, C> {
+    type Item = Result<i64, SnowflakeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.snowflake.gen())
+    }
+}
+
+/*
+
+a stream of gen results that ends right after the first error, see
+Snowflake::ids_until_error
+
+every error ends it, including transient ones, see SnowflakeError::is_transient,
+after which a new stream can be started to retry
+
+*/
+#[derive(Debug)]
+pub struct IdsUntilError<'a, C> {
+    snowflake: &'a mut Snowflake<C>,
+    failed: bool,
+}
+
+impl<C: Clock> Iterator for IdsUntilError<'_, C> {
+    type Item = Result<i64, SnowflakeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.snowflake.gen();
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+impl<C: Clock> Snowflake<C> {
+    pub fn ids(&mut self) -> Ids<'_, C> {
+        Ids { snowflake: self }
+    }
+
+    // e.g. snowflake.ids_until_error().take(n).collect::<Result<Vec<_>, _>>()
+    pub fn ids_until_error(&mut self) -> IdsUntilError<'_, C> {
+        IdsUntilError {
+            snowflake: self,
+            failed: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockClock, SnowflakeLayout};
+    use std::time::Duration;
+
+    fn stalling_snowflake() -> Snowflake<MockClock> {
+        // 4 ids per millisecond on a clock that never moves
+        Snowflake::builder(0)
+            .layout(SnowflakeLayout::new(44, 2, 2).unwrap())
+            .clock(MockClock::frozen(Duration::from_millis(1_000)))
+            .max_clock_stall(Duration::from_millis(1))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_ids_keep_yielding_errors() {
+        let mut snowflake = stalling_snowflake();
+        let results: Vec<_> = snowflake.ids().take(6).collect();
+        assert!(results[..4].iter().all(|result| result.is_ok()));
+        assert!(results[4..].iter().all(|result| result.is_err()));
+    }
+
+    #[test]
+    fn test_ids_until_error_stop_after_the_first_error() {
+        let mut snowflake = stalling_snowflake();
+        let layout = snowflake.layout();
+        let mut ids = snowflake.ids_until_error();
+        for seq in 0..4 {
+            assert_eq!(layout.sequence_of(ids.next().unwrap().unwrap()), seq);
+        }
+        let error = ids.next().unwrap().unwrap_err();
+        assert!(matches!(error, SnowflakeError::ClockStalledError { .. }));
+        assert!(error.is_transient());
+        assert!(ids.next().is_none());
+
+        snowflake.clock.advance(Duration::from_millis(1));
+        let ids: Result<Vec<i64>, _> = snowflake.ids_until_error().take(4).collect();
+        assert_eq!(ids.unwrap().len(), 4);
+        let ids: Result<Vec<i64>, _> = snowflake.ids_until_error().take(5).collect();
+        assert!(ids.is_err());
+    }
+}
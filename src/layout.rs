@@ -0,0 +1,462 @@
+use crate::SnowflakeError;
+
+/*
+
+how the 63 usable bits of an id are split between timestamp, sequence and
+service id, from most to least significant
+
+the default layout is the one described at the top of the crate, a layout may
+use less than 63 bits, in which case the unused most significant bits are zero
+
+the timestamp always comes first, while the sequence comes before the service
+id unless the layout puts the service id first, as twitter style ids do
+
+a layout can also carve a namespace, e.g. a tenant id, out of the top bits of
+the sequence, which then sits right after the timestamp, generators share one
+sequence between all namespaces, so every namespace gets fewer ids per
+millisecond, but ids stay unique across them
+
+likewise, a layout can carve a nonce out of the bottom bits of the sequence,
+which then takes up the lowest bits of the id, to be filled with random bits
+that make ids harder to guess
+
+*/
+
+const MAX_SEQUENCE_BITS: u32 = 31;
+const MAX_SERVICE_ID_BITS: u32 = 16;
+const MAX_NAMESPACE_BITS: u32 = 8;
+const MAX_NONCE_BITS: u32 = 31;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SnowflakeLayout {
+    timestamp_bits: u32,
+    sequence_bits: u32,
+    service_id_bits: u32,
+    service_id_first: bool,
+    namespace_bits: u32,
+    nonce_bits: u32,
+}
+
+impl SnowflakeLayout {
+    pub const DEFAULT: Self = Self {
+        timestamp_bits: 44,
+        sequence_bits: 17,
+        service_id_bits: 2,
+        service_id_first: false,
+        namespace_bits: 0,
+        nonce_bits: 0,
+    };
+
+    // discord ids have 42 timestamp bits, the top one of them only gets set after
+    // 2084, so generated ids leave it out to stay positive, and the 5 bit worker
+    // and process ids make up the service id as worker << 5 | process
+    pub const DISCORD: Self = Self {
+        timestamp_bits: 41,
+        sequence_bits: 12,
+        service_id_bits: 10,
+        service_id_first: true,
+        namespace_bits: 0,
+        nonce_bits: 0,
+    };
+
+    // twitter ids leave the top bit unset, so all of their 41 timestamp bits fit,
+    // with the 5 bit datacenter and worker ids as datacenter << 5 | worker
+    pub const TWITTER: Self = Self::DISCORD;
+
+    // instagram ids have 41 timestamp bits, the top one of them only gets set after
+    // 2045, so generated ids leave it out to stay positive, with the shard id as
+    // the service id
+    pub const INSTAGRAM: Self = Self {
+        timestamp_bits: 40,
+        sequence_bits: 10,
+        service_id_bits: 13,
+        service_id_first: true,
+        namespace_bits: 0,
+        nonce_bits: 0,
+    };
+
+    pub const fn new(
+        timestamp_bits: u32,
+        sequence_bits: u32,
+        service_id_bits: u32,
+    ) -> Result<Self, SnowflakeError> {
+        if timestamp_bits == 0
+            || sequence_bits > MAX_SEQUENCE_BITS
+            || service_id_bits > MAX_SERVICE_ID_BITS
+            || timestamp_bits + sequence_bits + service_id_bits > 63
+        {
+            return Err(SnowflakeError::InvalidLayoutError {
+                timestamp_bits,
+                sequence_bits,
+                service_id_bits,
+            });
+        }
+        Ok(Self {
+            timestamp_bits,
+            sequence_bits,
+            service_id_bits,
+            service_id_first: false,
+            namespace_bits: 0,
+            nonce_bits: 0,
+        })
+    }
+
+    // the same layout with the service id before the sequence
+    pub const fn with_service_id_first(mut self) -> Self {
+        self.service_id_first = true;
+        self
+    }
+
+    // the same layout with the top namespace_bits of the sequence used for a namespace
+    pub const fn with_namespace_bits(
+        mut self,
+        namespace_bits: u32,
+    ) -> Result<Self, SnowflakeError> {
+        if namespace_bits > MAX_NAMESPACE_BITS || namespace_bits > self.sequence_bits {
+            return Err(SnowflakeError::InvalidNamespaceBitsError {
+                namespace_bits,
+                sequence_bits: self.sequence_bits,
+            });
+        }
+        self.sequence_bits -= namespace_bits;
+        self.namespace_bits = namespace_bits;
+        Ok(self)
+    }
+
+    // the same layout with the bottom nonce_bits of the sequence used for a nonce
+    pub const fn with_nonce_bits(mut self, nonce_bits: u32) -> Result<Self, SnowflakeError> {
+        if nonce_bits > MAX_NONCE_BITS || nonce_bits > self.sequence_bits {
+            return Err(SnowflakeError::InvalidNonceBitsError {
+                nonce_bits,
+                sequence_bits: self.sequence_bits,
+            });
+        }
+        self.sequence_bits -= nonce_bits;
+        self.nonce_bits = nonce_bits;
+        Ok(self)
+    }
+
+    pub const fn timestamp_bits(&self) -> u32 {
+        self.timestamp_bits
+    }
+
+    pub const fn sequence_bits(&self) -> u32 {
+        self.sequence_bits
+    }
+
+    pub const fn service_id_bits(&self) -> u32 {
+        self.service_id_bits
+    }
+
+    pub const fn service_id_first(&self) -> bool {
+        self.service_id_first
+    }
+
+    pub const fn namespace_bits(&self) -> u32 {
+        self.namespace_bits
+    }
+
+    pub const fn nonce_bits(&self) -> u32 {
+        self.nonce_bits
+    }
+
+    pub const fn max_timestamp(&self) -> i64 {
+        ((1u64 << self.timestamp_bits) - 1) as i64
+    }
+
+    pub const fn max_sequence(&self) -> u32 {
+        ((1u64 << self.sequence_bits) - 1) as u32
+    }
+
+    pub const fn max_service_id(&self) -> u16 {
+        ((1u32 << self.service_id_bits) - 1) as u16
+    }
+
+    pub const fn max_namespace(&self) -> u8 {
+        ((1u32 << self.namespace_bits) - 1) as u8
+    }
+
+    pub const fn max_nonce(&self) -> u32 {
+        ((1u64 << self.nonce_bits) - 1) as u32
+    }
+
+    // the smallest id of the layout, with every field zero
+    pub const fn min_id(&self) -> i64 {
+        0
+    }
+
+    // the largest id of the layout, with every field at its maximum
+    pub const fn max_id(&self) -> i64 {
+        ((1u64 << (self.timestamp_shift() + self.timestamp_bits)) - 1) as i64
+    }
+
+    // packs the given fields into an id, masking each one to its width
+    pub const fn pack(&self, millis: i64, seq: u32, service_id: u16) -> i64 {
+        (millis & self.max_timestamp()) << self.timestamp_shift()
+            | ((seq & self.max_sequence()) as i64) << self.sequence_shift()
+            | ((service_id & self.max_service_id()) as i64) << self.service_id_shift()
+    }
+
+    // pack, with the id in the given namespace
+    pub const fn pack_with_namespace(
+        &self,
+        millis: i64,
+        namespace: u8,
+        seq: u32,
+        service_id: u16,
+    ) -> i64 {
+        self.pack(millis, seq, service_id)
+            | ((namespace & self.max_namespace()) as i64) << self.namespace_shift()
+    }
+
+    // the id with the given nonce, which only the id's nonce bits can hold
+    pub const fn with_nonce(&self, id: i64, nonce: u32) -> i64 {
+        id & !(self.max_nonce() as i64) | (nonce & self.max_nonce()) as i64
+    }
+
+    // the id with its service id replaced
+    pub const fn with_service_id(&self, id: i64, service_id: u16) -> i64 {
+        let mask = (self.max_service_id() as i64) << self.service_id_shift();
+        id & !mask | ((service_id & self.max_service_id()) as i64) << self.service_id_shift()
+    }
+
+    // splits an id back into (millis, seq, service_id), the inverse of pack
+    pub const fn decode(&self, id: i64) -> (i64, u32, u16) {
+        (
+            self.timestamp_bits_of(id),
+            self.sequence_of(id),
+            self.service_id_of(id),
+        )
+    }
+
+    // decodes ids[i] into out_ts[i], out_seq[i] and out_svc[i], one field per slice
+    // so the loop vectorizes, panics unless every slice has the length of ids
+    pub fn decode_many(
+        &self,
+        ids: &[i64],
+        out_ts: &mut [i64],
+        out_seq: &mut [u32],
+        out_svc: &mut [u16],
+    ) {
+        assert_eq!(ids.len(), out_ts.len());
+        assert_eq!(ids.len(), out_seq.len());
+        assert_eq!(ids.len(), out_svc.len());
+        for (((id, ts), seq), svc) in ids.iter().zip(out_ts).zip(out_seq).zip(out_svc) {
+            *ts = self.timestamp_bits_of(*id);
+            *seq = self.sequence_of(*id);
+            *svc = self.service_id_of(*id);
+        }
+    }
+
+    pub const fn timestamp_bits_of(&self, id: i64) -> i64 {
+        id >> self.timestamp_shift() & self.max_timestamp()
+    }
+
+    pub const fn namespace_of(&self, id: i64) -> u8 {
+        (id >> self.namespace_shift()) as u8 & self.max_namespace()
+    }
+
+    pub const fn nonce_of(&self, id: i64) -> u32 {
+        id as u32 & self.max_nonce()
+    }
+
+    pub const fn sequence_of(&self, id: i64) -> u32 {
+        (id >> self.sequence_shift()) as u32 & self.max_sequence()
+    }
+
+    pub const fn service_id_of(&self, id: i64) -> u16 {
+        (id >> self.service_id_shift()) as u16 & self.max_service_id()
+    }
+
+    const fn timestamp_shift(&self) -> u32 {
+        self.namespace_shift() + self.namespace_bits
+    }
+
+    const fn namespace_shift(&self) -> u32 {
+        self.nonce_bits + self.sequence_bits + self.service_id_bits
+    }
+
+    const fn sequence_shift(&self) -> u32 {
+        if self.service_id_first {
+            self.nonce_bits
+        } else {
+            self.nonce_bits + self.service_id_bits
+        }
+    }
+
+    const fn service_id_shift(&self) -> u32 {
+        if self.service_id_first {
+            self.nonce_bits + self.sequence_bits
+        } else {
+            self.nonce_bits
+        }
+    }
+}
+
+// timestamp/sequence/service id bits, like 44/17/2
+impl core::fmt::Display for SnowflakeLayout {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "{}/{}/{}",
+            self.timestamp_bits, self.sequence_bits, self.service_id_bits
+        )
+    }
+}
+
+impl Default for SnowflakeLayout {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MAX_17_BITS, MAX_2_BITS, MAX_44_BITS};
+
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_layout_decode_is_the_inverse_of_pack(
+            timestamp_bits in 1..=63u32,
+            sequence_bits in 0..=MAX_SEQUENCE_BITS,
+            service_id_bits in 0..=MAX_SERVICE_ID_BITS,
+            service_id_first: bool,
+            millis: i64,
+            seq: u32,
+            service_id: u16,
+        ) {
+            prop_assume!(timestamp_bits + sequence_bits + service_id_bits <= 63);
+            let mut layout = SnowflakeLayout::new(timestamp_bits, sequence_bits, service_id_bits).unwrap();
+            if service_id_first {
+                layout = layout.with_service_id_first();
+            }
+            let millis = millis & layout.max_timestamp();
+            let seq = seq & layout.max_sequence();
+            let service_id = service_id & layout.max_service_id();
+            let id = layout.pack(millis, seq, service_id);
+            prop_assert!(id >= 0);
+            prop_assert_eq!(layout.decode(id), (millis, seq, service_id));
+        }
+    }
+
+    #[test]
+    fn test_default_layout_matches_bit_anatomy() {
+        let layout = SnowflakeLayout::default();
+        assert_eq!(layout.max_timestamp(), MAX_44_BITS);
+        assert_eq!(layout.max_sequence(), MAX_17_BITS);
+        assert_eq!(layout.max_service_id(), MAX_2_BITS);
+        assert_eq!(layout.pack(5, 6, 3), crate::pack(5, 6, 3));
+    }
+
+    #[test]
+    fn test_service_id_first_layout_puts_service_id_above_sequence() {
+        let layout = SnowflakeLayout::new(41, 12, 10)
+            .unwrap()
+            .with_service_id_first();
+        assert_eq!(layout.pack(1, 2, 3), 1 << 22 | 3 << 12 | 2);
+        assert_eq!(layout.decode(1 << 22 | 3 << 12 | 2), (1, 2, 3));
+        assert_eq!(layout, SnowflakeLayout::DISCORD);
+    }
+
+    #[test]
+    fn test_namespace_sits_between_timestamp_and_sequence() {
+        let layout = SnowflakeLayout::DEFAULT.with_namespace_bits(3).unwrap();
+        assert_eq!(layout.sequence_bits(), 14);
+        assert_eq!(layout.max_namespace(), 7);
+        let id = layout.pack_with_namespace(5, 6, 7, 3);
+        assert_eq!(id, 5 << 19 | 6 << 16 | 7 << 2 | 3);
+        assert_eq!(layout.decode(id), (5, 7, 3));
+        assert_eq!(layout.namespace_of(id), 6);
+        assert_eq!(layout.namespace_of(layout.pack(5, 7, 3)), 0);
+
+        assert!(matches!(
+            SnowflakeLayout::DEFAULT.with_namespace_bits(9),
+            Err(SnowflakeError::InvalidNamespaceBitsError { .. })
+        ));
+        assert!(matches!(
+            SnowflakeLayout::new(44, 2, 2)
+                .unwrap()
+                .with_namespace_bits(3),
+            Err(SnowflakeError::InvalidNamespaceBitsError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_nonce_takes_the_lowest_bits() {
+        let layout = SnowflakeLayout::DEFAULT.with_nonce_bits(5).unwrap();
+        assert_eq!(layout.sequence_bits(), 12);
+        assert_eq!(layout.max_nonce(), 31);
+        let id = layout.with_nonce(layout.pack(5, 7, 3), 21);
+        assert_eq!(id, 5 << 19 | 7 << 7 | 3 << 5 | 21);
+        assert_eq!(layout.decode(id), (5, 7, 3));
+        assert_eq!(layout.nonce_of(id), 21);
+        assert_eq!(layout.nonce_of(layout.with_nonce(id, 32 + 9)), 9);
+
+        assert!(matches!(
+            SnowflakeLayout::new(44, 4, 2).unwrap().with_nonce_bits(5),
+            Err(SnowflakeError::InvalidNonceBitsError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_min_and_max_id() {
+        assert_eq!(SnowflakeLayout::DEFAULT.min_id(), 0);
+        assert_eq!(SnowflakeLayout::DEFAULT.max_id(), i64::MAX);
+        assert_eq!(
+            SnowflakeLayout::DEFAULT.max_id(),
+            crate::pack(MAX_44_BITS, MAX_17_BITS, MAX_2_BITS)
+        );
+        let layout = SnowflakeLayout::new(20, 10, 2)
+            .unwrap()
+            .with_namespace_bits(2)
+            .unwrap();
+        assert_eq!(layout.max_id(), (1 << 32) - 1);
+    }
+
+    #[test]
+    fn test_decode_many_matches_decode() {
+        let layout = SnowflakeLayout::DISCORD;
+        let ids: Vec<i64> = (0..100)
+            .map(|i| layout.pack(1_000 + i / 7, (i % 7) as u32, (i % 1_024) as u16))
+            .collect();
+        let mut ts = vec![0; ids.len()];
+        let mut seq = vec![0; ids.len()];
+        let mut svc = vec![0; ids.len()];
+        layout.decode_many(&ids, &mut ts, &mut seq, &mut svc);
+        for (i, id) in ids.iter().enumerate() {
+            assert_eq!(layout.decode(*id), (ts[i], seq[i], svc[i]));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_decode_many_rejects_short_outputs() {
+        let mut ts = [0; 2];
+        let mut seq = [0; 1];
+        let mut svc = [0; 2];
+        SnowflakeLayout::DEFAULT.decode_many(&[1, 2], &mut ts, &mut seq, &mut svc);
+    }
+
+    #[test]
+    fn test_layout_display() {
+        assert_eq!(SnowflakeLayout::DEFAULT.to_string(), "44/17/2");
+        assert_eq!(SnowflakeLayout::DISCORD.to_string(), "41/12/10");
+    }
+
+    #[test]
+    fn test_layout_rejects_invalid_widths() {
+        for (timestamp_bits, sequence_bits, service_id_bits) in
+            [(0, 17, 2), (44, 18, 2), (41, 32, 0), (30, 10, 17)]
+        {
+            assert!(matches!(
+                SnowflakeLayout::new(timestamp_bits, sequence_bits, service_id_bits),
+                Err(SnowflakeError::InvalidLayoutError { .. })
+            ));
+        }
+        assert!(SnowflakeLayout::new(41, 12, 10).is_ok());
+        assert!(SnowflakeLayout::new(20, 0, 0).is_ok());
+    }
+}
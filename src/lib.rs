@@ -1,6 +1,108 @@
-use std::sync::{Arc, Mutex};
-use std::thread::sleep;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "std")]
+use std::hash::{BuildHasher, Hasher};
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex, MutexGuard};
+#[cfg(feature = "std")]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "tokio")]
+mod async_snowflake;
+#[cfg(feature = "std")]
+mod atomic;
+#[cfg(feature = "std")]
+mod channel;
+#[cfg(feature = "bloomfilter")]
+mod checked;
+mod clock;
+mod decode;
+#[cfg(feature = "std")]
+mod discord;
+mod encoding;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod id;
+#[cfg(feature = "std")]
+mod instagram;
+mod iter;
+mod layout;
+mod merge;
+mod mnemonic;
+#[cfg(feature = "std")]
+mod multi;
+mod paced;
+#[cfg(feature = "std")]
+mod provider;
+mod registry;
+#[cfg(feature = "tower-http")]
+mod request_id;
+#[cfg(feature = "serde")]
+pub mod serde_string;
+#[cfg(feature = "std")]
+mod service_id;
+mod spread;
+mod state;
+mod stats;
+#[cfg(feature = "tracing")]
+mod trace;
+#[cfg(feature = "std")]
+mod twitter;
+#[cfg(feature = "std")]
+mod verify;
+
+#[cfg(feature = "tokio")]
+pub use async_snowflake::AsyncSnowflake;
+#[cfg(feature = "std")]
+pub use atomic::AtomicSnowflake;
+#[cfg(feature = "std")]
+pub use channel::ChannelSnowflake;
+#[cfg(feature = "bloomfilter")]
+pub use checked::CheckedSnowflake;
+#[cfg(feature = "wasm")]
+pub use clock::WasmClock;
+#[cfg(feature = "std")]
+pub use clock::{CachedClock, CoarseClock};
+pub use clock::{Clock, MockClock, SystemClock};
+pub use decode::SnowflakeParts;
+#[cfg(feature = "std")]
+pub use decode::{decode_lines, DecodeLineError, DecodedSnowflake};
+#[cfg(feature = "std")]
+pub use discord::{decode_discord, DiscordId, DISCORD_EPOCH};
+pub use id::SnowflakeId;
+#[cfg(feature = "std")]
+pub use instagram::{decode_instagram, InstagramId, INSTAGRAM_EPOCH};
+pub use iter::{Ids, IdsUntilError};
+pub use layout::SnowflakeLayout;
+pub use merge::SnowflakeMerger;
+#[cfg(feature = "std")]
+pub use multi::MultiServiceSnowflake;
+pub use paced::PacedSnowflake;
+#[cfg(feature = "std")]
+pub use provider::{
+    AsyncIdProvider, FakeIdProvider, FakeIdProviderExhaustedError, IdFuture, IdProviderError,
+};
+pub use registry::set_duplicate_service_id_check;
+pub use spread::Spread;
+pub use state::SnowflakeState;
+pub use stats::SnowflakeStats;
+#[cfg(feature = "std")]
+pub use twitter::{decode_twitter, TwitterId, TWITTER_EPOCH};
+#[cfg(feature = "std")]
+pub use verify::{VerifyReport, VERIFY_WINDOW};
+
+use registry::Registration;
 
 /*
 
@@ -27,61 +129,416 @@ maximum of 131071 unique ids per service per millisecond
 i.e. over 131 million unique ids per service per second
 i.e. over 524 million unique ids per second using 4 services
 
+Snowflake::throughput_probe measures how close a given machine gets to that
+
 */
 
-const MAX_17_BITS: u32 = 131071;
-const MAX_2_BITS: u16 = 3;
+// maximums of the default layout, for tests
+#[cfg(test)]
+const MAX_44_BITS: i64 = SnowflakeLayout::DEFAULT.max_timestamp();
+#[cfg(test)]
+const MAX_17_BITS: u32 = SnowflakeLayout::DEFAULT.max_sequence();
+#[cfg(test)]
+const MAX_2_BITS: u16 = SnowflakeLayout::DEFAULT.max_service_id();
+
+// how far ahead of a generator's clock Snowflake::is_plausible accepts timestamps
+pub const PLAUSIBLE_CLOCK_SKEW: Duration = Duration::from_secs(1);
+
+// the shortest time from the epoch to timestamp overflow a builder accepts,
+// unless it allows short lifespans
+pub const MIN_LIFESPAN: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+// how far apart the epochs back computed from every sample may be for
+// Snowflake::infer_epoch to agree on one
+pub const INFER_EPOCH_TOLERANCE: Duration = Duration::from_secs(60);
+
+static SATURATION_WARNED: AtomicBool = AtomicBool::new(false);
+
+// whether any generator in this process has come close to exhausting its sequence
+pub fn sequence_saturation_warned() -> bool {
+    SATURATION_WARNED.load(Ordering::Relaxed)
+}
+
+// packs the given fields into an id of the default layout, masking each one to its width
+pub const fn pack(millis: i64, seq: u32, service_id: u16) -> i64 {
+    SnowflakeLayout::DEFAULT.pack(millis, seq, service_id)
+}
+
+// splits an id of the default layout back into (millis, seq, service_id), the inverse of pack
+pub const fn decode(id: i64) -> (i64, u32, u16) {
+    SnowflakeLayout::DEFAULT.decode(id)
+}
+
+// decode for a whole slice of ids of the default layout, see SnowflakeLayout::decode_many
+pub fn decode_many(ids: &[i64], out_ts: &mut [i64], out_seq: &mut [u32], out_svc: &mut [u16]) {
+    SnowflakeLayout::DEFAULT.decode_many(ids, out_ts, out_seq, out_svc)
+}
+
+// milliseconds since epoch, i.e. the 44 bits after the signing bit
+pub const fn timestamp_bits_of(id: i64) -> i64 {
+    SnowflakeLayout::DEFAULT.timestamp_bits_of(id)
+}
+
+// the 17 bits between the timestamp and the service id
+pub const fn sequence_of(id: i64) -> u32 {
+    SnowflakeLayout::DEFAULT.sequence_of(id)
+}
+
+// the 2 least significant bits
+pub const fn service_id_of(id: i64) -> u16 {
+    SnowflakeLayout::DEFAULT.service_id_of(id)
+}
 
-pub struct ConcurrentSnowflake {
-    inner: Arc<Mutex<Snowflake>>,
+#[cfg(feature = "std")]
+pub struct ConcurrentSnowflake<C = SystemClock> {
+    inner: Arc<Mutex<Snowflake<C>>>,
+    poison_policy: PoisonPolicy,
+    // shared by every clone, so pausing one pauses them all
+    paused: Arc<AtomicBool>,
 }
 
+#[cfg(feature = "std")]
 impl ConcurrentSnowflake {
     pub fn new(service_id: u16) -> Result<Self, SnowflakeError> {
-        Ok(Self {
-            inner: Arc::new(Mutex::new(Snowflake::with_epoch(service_id, UNIX_EPOCH)?)),
-        })
+        Self::with_epoch(service_id, UNIX_EPOCH)
     }
 
     pub fn with_epoch(service_id: u16, epoch: SystemTime) -> Result<Self, SnowflakeError> {
-        Ok(Self {
-            inner: Arc::new(Mutex::new(Snowflake::with_epoch(service_id, epoch)?)),
-        })
+        Snowflake::builder(service_id)
+            .epoch(epoch)
+            .build_concurrent()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: Clock> ConcurrentSnowflake<C> {
+    // e.g. a MockClock whose clone a test keeps to drive time across threads
+    pub fn with_clock(
+        service_id: u16,
+        epoch: SystemTime,
+        clock: C,
+    ) -> Result<Self, SnowflakeError> {
+        Snowflake::builder(service_id)
+            .epoch(epoch)
+            .clock(clock)
+            .build_concurrent()
     }
 
-    pub fn clone(&self) -> Self {
+    /*
+
+    escape hatches to the shared generator, e.g. to keep it in a registry of arcs
+    or downgrade it to a Weak
+
+    every handle locking the mutex shares the one sequence, so ids stay unique as
+    long as the Snowflake is only reached through these arcs, never build another
+    Snowflake with the same service id alongside it, and never replace the one
+    inside the mutex with a new one, which restarts the sequence in the current
+    millisecond
+
+    from_arc starts out unpaused, with the default PoisonPolicy, and doesn't share
+    the paused flag of the ConcurrentSnowflake the arc came from
+
+    */
+
+    pub fn from_arc(inner: Arc<Mutex<Snowflake<C>>>) -> Self {
         Self {
-            inner: Arc::clone(&self.inner),
+            inner,
+            poison_policy: PoisonPolicy::default(),
+            paused: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    pub fn as_arc(&self) -> Arc<Mutex<Snowflake<C>>> {
+        Arc::clone(&self.inner)
+    }
+
     pub fn gen(&mut self) -> Result<i64, ConcurrentSnowflakeError> {
-        Ok(self
-            .inner
-            .lock()
-            .map_err(|_| ConcurrentSnowflakeError::PoisonError)?
-            .gen())
+        Ok(self.lock_unpaused()?.gen()?)
+    }
+
+    pub fn gen_u64(&mut self) -> Result<u64, ConcurrentSnowflakeError> {
+        Ok(self.gen()? as u64)
+    }
+
+    // gen_many under a single lock, so other threads wait for the whole batch
+    pub fn gen_many(&mut self, n: usize) -> Result<Vec<i64>, ConcurrentSnowflakeError> {
+        Ok(self.lock_unpaused()?.gen_many(n)?)
+    }
+
+    // makes gen return a PausedError on every clone until resumed, e.g. to halt
+    // minting ids during a migration, a gen call already past the check still
+    // finishes
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Release);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Release);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /*
+
+    switches every clone to a new service id and epoch
+
+    with a new service id, ids generated before and after differ in service id, so
+    the sequence starts over, and as the timestamps of the new epoch may be behind
+    the old ones, ids generated after may sort before ids generated before, going
+    back to a service id that was used before is only safe with an epoch that puts
+    its timestamps past the ones it had, which nothing here keeps track of
+
+    with the same service id only timestamps and sequences tell ids apart, so the
+    epoch can stay, which carries on with the sequence, or move back, which moves
+    the last timestamp ahead by as much, never behind any id generated before,
+    while moving it forward would have later timestamps repeat earlier ones, so
+    that is an EpochMovedForwardError that leaves the generator unchanged
+
+    */
+    pub fn reconfigure(
+        &self,
+        service_id: u16,
+        epoch: SystemTime,
+    ) -> Result<(), ConcurrentSnowflakeError> {
+        let epoch = epoch.duration_since(UNIX_EPOCH).map_err(|before| {
+            SnowflakeError::EpochBeforeUnixEpochError {
+                before: before.duration(),
+            }
+        })?;
+        let mut snowflake = self.lock()?;
+        let service_id =
+            service_id_field(service_id, snowflake.service_id_offset, snowflake.layout)?;
+        if service_id != snowflake.service_id {
+            snowflake._registration = Registration::new(service_id);
+            snowflake.last_millis = 0;
+            snowflake.reset_seq();
+        } else {
+            match snowflake.epoch.checked_sub(epoch) {
+                Some(back) => {
+                    let back = i64::try_from(back.as_millis()).unwrap_or(i64::MAX);
+                    snowflake.last_millis = snowflake.last_millis.saturating_add(back);
+                }
+                None => {
+                    return Err(SnowflakeError::EpochMovedForwardError {
+                        service_id: snowflake.service_id(),
+                        by: epoch - snowflake.epoch,
+                    }
+                    .into())
+                }
+            }
+        }
+        snowflake.service_id = service_id;
+        snowflake.epoch = epoch;
+        Ok(())
+    }
+
+    // switches every clone to another WaitStrategy, e.g. to spin through a traffic
+    // spike, applying from the next wait on, a gen call waiting already keeps the
+    // lock, and with it the strategy it started with
+    pub fn set_wait_strategy(
+        &self,
+        wait_strategy: WaitStrategy,
+    ) -> Result<(), ConcurrentSnowflakeError> {
+        self.lock()?.wait_strategy = wait_strategy;
+        Ok(())
+    }
+
+    // the lock for generating, every path minting ids goes through it so none of
+    // them skips the paused check
+    fn lock_unpaused(&self) -> Result<MutexGuard<'_, Snowflake<C>>, ConcurrentSnowflakeError> {
+        if self.paused.load(Ordering::Acquire) {
+            return Err(ConcurrentSnowflakeError::PausedError);
+        }
+        self.lock()
     }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Snowflake<C>>, ConcurrentSnowflakeError> {
+        match self.inner.lock() {
+            Ok(guard) => Ok(guard),
+            Err(poisoned) => match self.poison_policy {
+                PoisonPolicy::Propagate => Err(ConcurrentSnowflakeError::PoisonError),
+                PoisonPolicy::Recover => {
+                    self.inner.clear_poison();
+                    Ok(poisoned.into_inner())
+                }
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C> Clone for ConcurrentSnowflake<C> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            poison_policy: self.poison_policy,
+            paused: Arc::clone(&self.paused),
+        }
+    }
+}
+
+/*
+
+the concurrency contract, checked at compile time
+
+a Snowflake can be moved to another thread but needs &mut to generate, so
+sharing one between threads takes a ConcurrentSnowflake, which has to stay Send
+and Sync for that, with any clock that is Send itself
+
+*/
+#[cfg(feature = "std")]
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send::<Snowflake>();
+    assert_send::<Snowflake<CachedClock>>();
+    assert_send::<MultiServiceSnowflake>();
+    assert_send::<ChannelSnowflake>();
+    assert_send_sync::<ConcurrentSnowflake>();
+    assert_send_sync::<ConcurrentSnowflake<CachedClock>>();
+    assert_send_sync::<ConcurrentSnowflake<MockClock>>();
+    assert_send_sync::<AtomicSnowflake>();
+    assert_send_sync::<AtomicSnowflake<MockClock>>();
+};
+
+/*
+
+what to do when a thread panicked while holding the generator's lock
+
+recovering keeps the generator usable, but the panic may have interrupted a gen
+call halfway through, i.e. after the timestamp was recorded but before the
+sequence was advanced, so the next id could repeat the one being generated
+when the panic happened
+
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PoisonPolicy {
+    // return a PoisonError on every gen call after the lock was poisoned
+    #[default]
+    Propagate,
+    // clear the poison and keep generating
+    Recover,
+}
+
+/*
+
+how to wait for the clock to reach the next millisecond once the sequence is
+exhausted, or to catch up after going back
+
+sleeping frees the cpu, but usually oversleeps by tens of microseconds or more,
+while spinning re-reads the clock until it moves, which catches the new
+millisecond right away at the cost of the cpu time spent until then, spinning
+only for a while bounds that cost, and sleeps if the millisecond didn't tick
+
+spinning waits for the clock to move on its own, so it never ends with a clock
+that only moves when slept on, like a MockClock no other thread advances
+
+without std the time spent spinning is read off the clock too, so a clock that
+stalls stops that time as well, SpinThenSleep then never gets to sleeping and
+max_clock_stall never counts the spinning
+
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaitStrategy {
+    #[default]
+    Sleep,
+    Spin,
+    SpinThenSleep {
+        spin_for: Duration,
+    },
 }
 
+// how write_ids frames every id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdFormat {
+    // the decimal id followed by a newline
+    #[default]
+    Decimal,
+    // the 8 bytes of the id, most significant first
+    BigEndian,
+}
+
+/*
+
+what to do once the sequence of a millisecond is exhausted
+
+waiting for the clock keeps every timestamp true to it, see WaitStrategy for how,
+borrowing instead moves on to the next millisecond right away, so ids keep
+flowing through bursts at the cost of timestamps running ahead of the clock,
+which corrects itself once the clock catches up with them
+
+the drift is bounded by max_drift, once the next millisecond would be further
+ahead of the clock than that, the generator waits for the clock again, and as
+the generator can't tell a clock that went back from one it ran ahead of, going
+back by up to max_drift also keeps counting forward without waiting or erroring,
+whatever max_backward_skew is
+
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExhaustionPolicy {
+    #[default]
+    Wait,
+    BorrowTimestamp {
+        max_drift: Duration,
+    },
+}
+
+/*
+
+what to do once the time since the epoch no longer fits the timestamp bits
+
+erroring is the only policy that keeps every guarantee, saturating keeps ids
+unique and sorted but squeezes every later id into the last millisecond, so it
+fails once that millisecond's sequence runs out, and wrapping starts over from
+timestamp zero, so its ids repeat the ones generated one lifespan earlier and
+sort before every id generated before the wrap
+
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    // return a TimestampOverflowError
+    #[default]
+    Error,
+    // keep generating with the maximum timestamp until its sequence is exhausted
+    Saturate,
+    // keep only the lowest timestamp bits
+    Wrap,
+}
+
+#[cfg(feature = "std")]
 #[derive(Debug)]
+#[must_use]
 pub enum ConcurrentSnowflakeError {
     PoisonError,
+    PausedError,
     SnowflakeError(SnowflakeError),
 }
 
-impl std::fmt::Display for ConcurrentSnowflakeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+#[cfg(feature = "std")]
+impl core::fmt::Display for ConcurrentSnowflakeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
             Self::PoisonError => write!(
                 f,
                 "lock was poisoned during a previous access and can no longer be locked"
             ),
+            Self::PausedError => write!(f, "generator is paused"),
             Self::SnowflakeError(e) => e.fmt(f),
         }
     }
 }
 
+#[cfg(feature = "std")]
+impl From<SnowflakeError> for ConcurrentSnowflakeError {
+    fn from(e: SnowflakeError) -> Self {
+        Self::SnowflakeError(e)
+    }
+}
+
+#[cfg(feature = "std")]
 impl std::error::Error for ConcurrentSnowflakeError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
@@ -92,97 +549,3423 @@ impl std::error::Error for ConcurrentSnowflakeError {
 }
 
 #[derive(Debug)]
-pub struct Snowflake {
-    epoch: SystemTime,
+pub struct Snowflake<C = SystemClock> {
+    clock: C,
+    // since UNIX_EPOCH, like the time of the clock, so no SystemTime is needed
+    epoch: Duration,
+    layout: SnowflakeLayout,
     service_id: u16,
     last_millis: i64,
+    // how many ids were generated in the current millisecond
     seq: u32,
+    // where the sequence starts in the current millisecond
+    seq_offset: u32,
+    // how many ids this generator has ever generated
+    generated: u64,
+    // the rest of SnowflakeStats, only ever counted on the slow path of gen
+    sequence_rollovers: u64,
+    millis_waited: u64,
+    clock_regressions: u64,
+    random_sequence_start: bool,
+    max_clock_stall: Option<Duration>,
+    saturation_hook: Option<fn()>,
+    max_backward_skew: Option<Duration>,
+    overflow_policy: OverflowPolicy,
+    service_id_offset: u16,
+    wait_strategy: WaitStrategy,
+    exhaustion_policy: ExhaustionPolicy,
+    #[cfg(feature = "tracing")]
+    trace_level: tracing::Level,
+    // only held to unregister the service id once dropped
+    _registration: Registration,
 }
 
 impl Snowflake {
+    #[cfg(feature = "std")]
     pub fn new(service_id: u16) -> Result<Self, SnowflakeError> {
-        Ok(Self::with_epoch(service_id, UNIX_EPOCH)?)
+        Self::with_epoch(service_id, UNIX_EPOCH)
     }
 
+    #[cfg(feature = "std")]
     pub fn with_epoch(service_id: u16, epoch: SystemTime) -> Result<Self, SnowflakeError> {
-        if service_id > MAX_2_BITS {
-            return Err(SnowflakeError::InvalidServiceIdError);
+        Self::builder(service_id).epoch(epoch).build()
+    }
+
+    // e.g. SnowflakeLayout::new(41, 12, 10) for up to 1024 services generating up
+    // to 4096 ids per millisecond each
+    #[cfg(feature = "std")]
+    pub fn with_layout(service_id: u16, layout: SnowflakeLayout) -> Result<Self, SnowflakeError> {
+        Self::builder(service_id).layout(layout).build()
+    }
+
+    // continues after last_id, e.g. the last id that survived a crash, so that no
+    // id up to and including it is generated again
+    #[cfg(feature = "std")]
+    pub fn resume_from(
+        service_id: u16,
+        epoch: SystemTime,
+        last_id: i64,
+    ) -> Result<Self, SnowflakeError> {
+        Self::builder(service_id)
+            .epoch(epoch)
+            .resume_from(last_id)
+            .build()
+    }
+
+    /*
+
+    picks a service id at random, for ephemeral workers that have no stable
+    ordinal to use as one, from the given seed if there is one
+
+    nothing prevents two workers from picking the same service id, with n workers
+    picking from the 2^b service ids of the layout the chance of a collision is
+    about 1 - e^(-n * (n - 1) / 2^(b + 1)), e.g.
+     - 2 bits (the default): 2 workers 25%, 3 workers 63%
+     - 10 bits: 10 workers 4%, 40 workers 53%
+     - 16 bits: 100 workers 7%, 300 workers 50%
+    so random service ids need a layout with plenty of service id bits
+
+    */
+    #[cfg(feature = "rand")]
+    pub fn with_random_service_id(
+        layout: SnowflakeLayout,
+        seed: Option<u64>,
+    ) -> Result<Self, SnowflakeError> {
+        use rand::rngs::StdRng;
+        use rand::{RngExt, SeedableRng};
+
+        let service_ids = 0..=layout.max_service_id();
+        let service_id = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed).random_range(service_ids),
+            None => rand::rng().random_range(service_ids),
+        };
+        Self::with_layout(service_id, layout)
+    }
+
+    // with_epoch, with the epoch given as an rfc 3339 date like "2020-01-01T00:00:00Z",
+    // which must not be in the future
+    #[cfg(feature = "time")]
+    pub fn with_epoch_rfc3339(service_id: u16, epoch: &str) -> Result<Self, SnowflakeError> {
+        use time::format_description::well_known::Rfc3339;
+        use time::OffsetDateTime;
+
+        let parsed = OffsetDateTime::parse(epoch, &Rfc3339).map_err(|_| {
+            SnowflakeError::InvalidEpochStringError {
+                input: epoch.to_string(),
+            }
+        })?;
+        let epoch = SystemTime::from(parsed);
+        if epoch > SystemTime::now() {
+            return Err(SnowflakeError::EpochInFutureError { epoch });
         }
-        Ok(Self {
-            epoch,
-            service_id,
-            last_millis: 0,
-            seq: 0,
-        })
+        Self::with_epoch(service_id, epoch)
+    }
+
+    pub fn builder(service_id: u16) -> SnowflakeBuilder {
+        SnowflakeBuilder::new(service_id)
+    }
+
+    // the epoch of a foreign scheme, solved from ids of the given layout along with
+    // roughly when each was created, as the median of the epochs every sample
+    // implies, or None without samples, or if those epochs span more than
+    // INFER_EPOCH_TOLERANCE
+    #[cfg(feature = "std")]
+    pub fn infer_epoch(
+        samples: &[(i64, SystemTime)],
+        layout: &SnowflakeLayout,
+    ) -> Option<SystemTime> {
+        let mut epochs = samples
+            .iter()
+            .map(|(id, created)| {
+                if *id < 0 {
+                    return None;
+                }
+                let since_epoch = Duration::from_millis(layout.timestamp_bits_of(*id) as u64);
+                created.checked_sub(since_epoch)
+            })
+            .collect::<Option<Vec<SystemTime>>>()?;
+        epochs.sort();
+        let (first, last) = (epochs.first()?, epochs.last()?);
+        if last.duration_since(*first).ok()? > INFER_EPOCH_TOLERANCE {
+            return None;
+        }
+        Some(epochs[epochs.len() / 2])
+    }
+}
+
+impl<C: Clock> Snowflake<C> {
+    #[inline]
+    pub fn gen(&mut self) -> Result<i64, SnowflakeError> {
+        let (_, millis) = self.get_time()?;
+
+        if millis > self.layout.max_timestamp()
+            || millis < self.last_millis
+            || (millis == self.last_millis && self.seq > self.layout.max_sequence())
+        {
+            return self.gen_slow(millis);
+        }
+        if millis > self.last_millis {
+            // new millisecond, reset sequence
+            self.reset_seq();
+        }
+        // a new millisecond, or the same one with sequence to spare
+        self.emit(millis, false)
     }
 
-    pub fn gen(&mut self) -> i64 {
-        let (current_time, mut millis) = self.get_time();
+    // the timestamp overflowed, the clock went backward or the sequence is exhausted,
+    // all of which may wait or fail
+    #[cold]
+    #[inline(never)]
+    fn gen_slow(&mut self, mut millis: i64) -> Result<i64, SnowflakeError> {
+        let mut waited = false;
+        let max_timestamp = self.layout.max_timestamp();
+        let overflow_error = SnowflakeError::TimestampOverflowError {
+            millis: millis as u128,
+            max: max_timestamp,
+        };
+
+        if millis > max_timestamp {
+            match self.overflow_policy {
+                OverflowPolicy::Error => return Err(overflow_error),
+                OverflowPolicy::Saturate => millis = max_timestamp,
+                // pack masks the timestamp, while it's still compared unmasked here
+                OverflowPolicy::Wrap => {}
+            }
+        }
+
+        let clock_millis = millis;
+        if millis < self.last_millis {
+            if self.within_drift(self.last_millis - millis) {
+                // the clock is behind a borrowed millisecond, keep counting from it
+                millis = self.last_millis;
+            } else {
+                self.clock_regressions += 1;
+                if let Some(max_backward_skew) = self.max_backward_skew {
+                    let behind = Duration::from_millis((self.last_millis - millis) as u64);
+                    if behind > max_backward_skew {
+                        return Err(SnowflakeError::ClockWentBackwardError { behind });
+                    }
+                    // small skews are waited out, instead of borrowing timestamps from the future
+                    millis = self.wait_for_millis(self.last_millis)?;
+                    self.millis_waited += (millis - clock_millis) as u64;
+                    waited = true;
+                } else {
+                    // the clock is behind the last id, e.g. the one resumed from, keep
+                    // counting from it
+                    millis = self.last_millis;
+                }
+            }
+        }
 
         if millis > self.last_millis {
             // new millisecond, reset sequence
-            self.seq = 0;
-        } else if self.seq == MAX_17_BITS {
-            // sequence was exhausted in the same millisecond, wait until next millisecond
-            let elapsed_micros = current_time
-                .duration_since(self.epoch)
-                .unwrap()
-                .subsec_micros();
-            let sleep_duration = Duration::from_micros((1_000 - elapsed_micros) as u64);
-            sleep(sleep_duration);
-            millis += 1;
+            self.reset_seq();
+        } else if self.seq > self.layout.max_sequence() {
+            if self.overflow_policy == OverflowPolicy::Saturate && millis >= max_timestamp {
+                // a saturated timestamp never gets to a next millisecond
+                return Err(overflow_error);
+            }
+            self.sequence_rollovers += 1;
+            let next_millis = self.last_millis + 1;
+            if next_millis <= max_timestamp && self.within_drift(next_millis - clock_millis) {
+                // borrow the next millisecond before the clock gets there
+                millis = next_millis;
+            } else {
+                // sequence was exhausted in the same millisecond, wait until next millisecond
+                let before = millis;
+                millis = self.wait_next_millis()?;
+                self.millis_waited += (millis - before) as u64;
+                waited = true;
+            }
+            self.reset_seq();
+        }
+        self.emit(millis, waited)
+    }
+
+    // whether a timestamp ahead of the clock by millis may be borrowed
+    fn within_drift(&self, millis: i64) -> bool {
+        match self.exhaustion_policy {
+            ExhaustionPolicy::Wait => false,
+            ExhaustionPolicy::BorrowTimestamp { max_drift } => {
+                millis as u128 <= max_drift.as_millis()
+            }
         }
+    }
 
+    #[inline]
+    fn emit(&mut self, millis: i64, waited: bool) -> Result<i64, SnowflakeError> {
         self.last_millis = millis;
-        millis << 19 | ((self.next_seq()) << 2) as i64 | self.service_id as i64
+        self.generated += 1;
+        let seq = self.next_seq();
+        #[cfg(feature = "tracing")]
+        trace::gen_event(self.trace_level, millis, seq, self.service_id, waited);
+        #[cfg(not(feature = "tracing"))]
+        let _ = waited;
+        Ok(self.layout.pack(millis, seq, self.service_id))
     }
 
-    fn next_seq(&mut self) -> u32 {
-        self.seq = (self.seq + 1) % MAX_17_BITS;
-        self.seq
+    // gen, typed so ids don't get mixed up with other integers
+    pub fn gen_id(&mut self) -> Result<SnowflakeId, SnowflakeError> {
+        SnowflakeId::new(self.gen()?)
     }
 
-    fn get_time(&self) -> (SystemTime, i64) {
-        let current_time = SystemTime::now();
-        let millis = current_time.duration_since(self.epoch).unwrap().as_millis() as i64;
-        (current_time, millis)
+    // gen, with the id in the given namespace of a layout with namespace bits, all
+    // namespaces share the generator's sequence, so ids are unique across them
+    pub fn gen_for_namespace(&mut self, namespace: u8) -> Result<i64, SnowflakeError> {
+        let max = self.layout.max_namespace();
+        if namespace > max {
+            return Err(SnowflakeError::InvalidNamespaceError { namespace, max });
+        }
+        let id = self.gen()?;
+        Ok(id | self.layout.pack_with_namespace(0, namespace, 0, 0))
     }
-}
 
-#[derive(Debug)]
-pub enum SnowflakeError {
-    InvalidServiceIdError,
-}
+    // gen, with the nonce bits of the layout filled from rng, so ids can't be guessed
+    // from the ones before them, every nonce bit halves the ids a millisecond can
+    // hold, which are still unique since the sequence isn't shared with the nonce
+    #[cfg(feature = "rand")]
+    pub fn gen_with_nonce<R: rand::CryptoRng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+    ) -> Result<i64, SnowflakeError> {
+        let id = self.gen()?;
+        Ok(self.layout.with_nonce(id, rng.next_u32()))
+    }
 
-impl std::fmt::Display for SnowflakeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            Self::InvalidServiceIdError => write!(f, "service id must fit in 2 bits"),
+    // generates and discards ids as fast as possible for the given duration of real
+    // time, returning how many it generated, to check what a machine can sustain
+    #[cfg(feature = "std")]
+    pub fn throughput_probe(&mut self, duration: Duration) -> Result<u64, SnowflakeError> {
+        let start = Instant::now();
+        let mut count = 0;
+        while start.elapsed() < duration {
+            // reading the time for every id would cost about as much as generating it
+            for _ in 0..1_024 {
+                std::hint::black_box(self.gen()?);
+            }
+            count += 1_024;
         }
+        Ok(count)
     }
-}
 
-impl std::error::Error for SnowflakeError {}
+    // gen, with the key's shard, a stable hash of it into the service id space, as
+    // the service id, so ids of the same key always land on the same shard, the
+    // generator then uses every service id, so no other generator may share its layout
+    // and epoch, ids stay unique since all shards share its sequence
+    pub fn gen_for_key(&mut self, key: &[u8]) -> Result<i64, SnowflakeError> {
+        let id = self.gen()?;
+        let shards = self.layout.max_service_id() as u64 + 1;
+        let shard = (fnv1a(key) % shards) as u16;
+        Ok(self.layout.with_service_id(id, shard))
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // appends n new ids to out, e.g. a parameter buffer reused across bulk inserts,
+    // if generating fails the ids generated before it stay appended, the clock is
+    // only read once per millisecond, the first id of which goes through gen and
+    // the rest of its sequence is handed out right away
+    pub fn append_n(&mut self, out: &mut Vec<i64>, n: usize) -> Result<(), SnowflakeError> {
+        out.reserve(n);
+        let end = out.len() + n;
+        while out.len() < end {
+            out.push(self.gen()?);
+            while out.len() < end && self.seq <= self.layout.max_sequence() {
+                out.push(self.emit(self.last_millis, false)?);
+            }
+        }
+        Ok(())
+    }
 
-    const NUM_IDS: u64 = 1_000_000;
+    // append_n into a new vec, spilling into as many milliseconds as n ids take
+    pub fn gen_many(&mut self, n: usize) -> Result<Vec<i64>, SnowflakeError> {
+        let mut ids = Vec::new();
+        self.append_n(&mut ids, n)?;
+        Ok(ids)
+    }
 
-    #[test]
-    fn test_snowflake_creates_unique_positive_ids() {
-        let mut snowflake = Snowflake::new(0).unwrap();
-        let mut ids: Vec<i64> = Vec::new();
-        for _ in 0..NUM_IDS {
-            ids.push(snowflake.gen());
+    // generates n ids straight into w, e.g. a socket of an id dispensing service,
+    // failing to generate is returned as an io error wrapping the SnowflakeError,
+    // w isn't flushed, and ids written before an error stay written
+    #[cfg(feature = "std")]
+    pub fn write_ids(&mut self, w: &mut impl Write, n: usize, format: IdFormat) -> io::Result<()> {
+        for _ in 0..n {
+            let id = self.gen().map_err(io::Error::other)?;
+            match format {
+                IdFormat::Decimal => writeln!(w, "{id}")?,
+                IdFormat::BigEndian => w.write_all(&id.to_be_bytes())?,
+            }
         }
-        ids.sort();
-        ids.dedup();
-        ids = ids.into_iter().filter(|id| *id > 0).collect();
-        assert_eq!(ids.len(), NUM_IDS as usize);
+        Ok(())
+    }
+
+    /*
+
+    reserves n ids that are consecutive integers, e.g. a block of primary keys, as
+    the half-open range of them
+
+    ids are only consecutive within a millisecond, and only in layouts with the
+    whole sequence in the lowest bits, i.e. with the service id first, no nonce
+    and no namespace carved out of the top of the sequence, so the block must fit in what's left of the current millisecond's sequence, or
+    this returns a RangeSpansMillisecondsError, after which advance_to_next_millis
+    gives the next range a whole millisecond, this fails after generating the
+    first id of the block, which is lost, while an empty range generates none
+
+    */
+    pub fn gen_range(&mut self, n: u32) -> Result<Range<i64>, SnowflakeError> {
+        if !self.layout.service_id_first() && self.layout.service_id_bits() > 0
+            || self.layout.nonce_bits() > 0
+            || self.layout.namespace_bits() > 0
+        {
+            return Err(SnowflakeError::NonContiguousLayoutError {
+                layout: self.layout,
+            });
+        }
+        if n == 0 {
+            return Ok(0..0);
+        }
+        let first = self.gen()?;
+        let max_seq = self.layout.max_sequence();
+        // neither may the sequence run out nor wrap around a random start
+        let remaining = (max_seq + 1 - self.seq).min(max_seq - self.layout.sequence_of(first));
+        if n - 1 > remaining {
+            return Err(SnowflakeError::RangeSpansMillisecondsError {
+                n,
+                remaining: remaining + 1,
+            });
+        }
+        self.warn_saturation(n - 1);
+        self.seq += n - 1;
+        self.generated += (n - 1) as u64;
+        Ok(first..first + n as i64)
+    }
+
+    // generates n ids, along with the indices of the ids whose timestamp is later
+    // than the one before them, so ids[boundaries[i]..boundaries[i + 1]] share a
+    // millisecond without decoding them
+    pub fn gen_batch_with_boundaries(
+        &mut self,
+        n: usize,
+    ) -> Result<(Vec<i64>, Vec<usize>), SnowflakeError> {
+        let mut ids = Vec::with_capacity(n);
+        let mut boundaries = Vec::new();
+        for i in 0..n {
+            let last_millis = self.last_millis;
+            ids.push(self.gen()?);
+            if i > 0 && self.last_millis != last_millis {
+                boundaries.push(i);
+            }
+        }
+        Ok((ids, boundaries))
+    }
+
+    // also returns how many ids this generator has generated so far, including this
+    // one, which keeps increasing by exactly one no matter what the clock does
+    pub fn gen_with_ordinal(&mut self) -> Result<(i64, u64), SnowflakeError> {
+        let id = self.gen()?;
+        Ok((id, self.generated))
+    }
+
+    // waits until the clock is past the millisecond of the last id and starts a fresh
+    // sequence there, so ids generated before and after never share a millisecond
+    pub fn advance_to_next_millis(&mut self) -> Result<(), SnowflakeError> {
+        self.last_millis = self.wait_next_millis()?;
+        self.reset_seq();
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn epoch(&self) -> SystemTime {
+        UNIX_EPOCH + self.epoch
+    }
+
+    // epoch, as the time since UNIX_EPOCH, which is all there is without std
+    pub fn epoch_since_unix_epoch(&self) -> Duration {
+        self.epoch
+    }
+
+    pub fn service_id(&self) -> u16 {
+        self.service_id + self.service_id_offset
+    }
+
+    pub fn layout(&self) -> SnowflakeLayout {
+        self.layout
+    }
+
+    // bounds of every id the layout can hold, e.g. for a database check constraint
+    pub const fn min_id(&self) -> i64 {
+        self.layout.min_id()
+    }
+
+    pub const fn max_id(&self) -> i64 {
+        self.layout.max_id()
+    }
+
+    // how many generators can run at the same time without colliding ids, each of
+    // them needs its own service id, which nothing here coordinates, so deployments
+    // have to hand them out themselves and keep their replica count within this
+    pub fn max_concurrent_services(&self) -> u32 {
+        self.layout.max_service_id() as u32 + 1
+    }
+
+    // the most ids every generator of this layout together can generate in the
+    // whole milliseconds from start to end, zero unless end is after start,
+    // saturating at u64::MAX, regardless of whether the window fits the timestamp
+    // range, see validate_for for that
+    #[cfg(feature = "std")]
+    pub fn capacity_between(&self, start: SystemTime, end: SystemTime) -> u64 {
+        let millis = end.duration_since(start).unwrap_or_default().as_millis();
+        let per_millis =
+            (self.layout.max_sequence() as u64 + 1) * self.max_concurrent_services() as u64;
+        u64::try_from(millis)
+            .unwrap_or(u64::MAX)
+            .saturating_mul(per_millis)
+    }
+
+    // distinct service ids for replica_count generators sharing this generator's
+    // layout and service id offset, the first replica_count ones in order, so the
+    // same count always gets the same ids, e.g. for deploy tooling handing out one
+    // per replica
+    pub fn assign_service_ids(&self, replica_count: u16) -> Result<Vec<u16>, LayoutProblem> {
+        let max_services = self.max_concurrent_services();
+        if replica_count as u32 > max_services {
+            return Err(LayoutProblem::TooManyServicesError {
+                service_count: replica_count,
+                max_services,
+            });
+        }
+        Ok((0..replica_count)
+            .map(|i| i + self.service_id_offset)
+            .collect())
+    }
+
+    // a pre-flight check that this generator's layout and epoch can serve a
+    // deployment of service_count generators, each generating up to expected_qps ids
+    // per second, until the given time
+    #[cfg(feature = "std")]
+    pub fn validate_for(
+        &self,
+        expected_qps: u64,
+        service_count: u16,
+        until: SystemTime,
+    ) -> Result<(), LayoutProblem> {
+        let max_qps = (self.layout.max_sequence() as u64 + 1) * 1_000;
+        if expected_qps > max_qps {
+            return Err(LayoutProblem::ThroughputTooHighError {
+                expected_qps,
+                max_qps,
+            });
+        }
+        let max_services = self.max_concurrent_services();
+        if service_count as u32 > max_services {
+            return Err(LayoutProblem::TooManyServicesError {
+                service_count,
+                max_services,
+            });
+        }
+        // the first millisecond that doesn't fit the timestamp bits anymore
+        let overflows_at =
+            self.epoch() + Duration::from_millis(self.layout.max_timestamp() as u64 + 1);
+        if until >= overflows_at {
+            return Err(LayoutProblem::TimestampOverflowError {
+                until,
+                overflows_at,
+            });
+        }
+        Ok(())
+    }
+
+    // everything that decides which ids a generator can produce, generators with
+    // the same config produce overlapping ids, so its service id is the one stored
+    // in ids, without any offset
+    #[cfg(feature = "std")]
+    pub fn config(&self) -> SnowflakeConfig {
+        SnowflakeConfig {
+            epoch: self.epoch(),
+            service_id: self.service_id,
+            layout: self.layout,
+        }
+    }
+
+    // time left until the timestamp field no longer fits in its bits
+    pub fn remaining_timestamp_range(&self) -> Duration {
+        // a clock before the epoch has all of the range still ahead of it
+        let millis = self
+            .elapsed()
+            .map_or(0, |elapsed| elapsed.as_millis() as i64);
+        Duration::from_millis((self.layout.max_timestamp() - millis).max(0) as u64)
+    }
+
+    // gen for storage and protocols with unsigned ids, ids never set the signing bit
+    // so the cast is lossless, and the top bit of the u64 is always 0
+    pub fn gen_u64(&mut self) -> Result<u64, SnowflakeError> {
+        Ok(self.gen()? as u64)
+    }
+
+    // an id source for apis that take unsigned ids, see gen_u64, it panics if
+    // generating fails since the source can't report errors
+    pub fn as_u64_source(&mut self) -> impl FnMut() -> u64 + '_ {
+        move || self.gen_u64().expect("failed to generate snowflake id")
+    }
+
+    // decode, without the epoch applied to the timestamp
+    pub fn parts(&self, id: i64) -> Result<SnowflakeParts, SnowflakeError> {
+        let (timestamp_millis, seq, service_id) = self.layout.decode(SnowflakeId::new(id)?.get());
+        Ok(SnowflakeParts {
+            timestamp_millis,
+            seq,
+            service_id: service_id.saturating_add(self.service_id_offset),
+        })
+    }
+
+    // decodes an id generated by this generator, or one configured the same way
+    #[cfg(feature = "std")]
+    pub fn decode(&self, id: i64) -> Result<DecodedSnowflake, SnowflakeError> {
+        let mut decoded = DecodedSnowflake::with_layout(id, self.epoch(), self.layout)?;
+        decoded.service_id = decoded.service_id.saturating_add(self.service_id_offset);
+        Ok(decoded)
+    }
+
+    // whether the id could have been generated with this generator's epoch and layout
+    // by now, allowing for PLAUSIBLE_CLOCK_SKEW between clocks, a cheap check to
+    // reject forged ids claiming impossible times before trusting them
+    pub fn is_plausible(&self, id: i64) -> bool {
+        let Ok(elapsed) = self.elapsed() else {
+            return false;
+        };
+        let latest = (elapsed + PLAUSIBLE_CLOCK_SKEW).as_millis();
+        id >= 0 && id <= self.layout.max_id() && self.layout.timestamp_bits_of(id) as u128 <= latest
+    }
+
+    // an id claiming to be generated offset from now, e.g. to test code rejecting
+    // future dated ids or not yet expired ones, without touching the state of the
+    // generator, so it always gets sequence 0 and repeats the id gen returns for
+    // the first id of that millisecond, never store it next to generated ids
+    pub fn gen_future(&self, offset: Duration) -> Result<i64, SnowflakeError> {
+        let millis = self.elapsed()?.saturating_add(offset).as_millis();
+        let max = self.layout.max_timestamp();
+        if millis > max as u128 {
+            return Err(SnowflakeError::TimestampOverflowError { millis, max });
+        }
+        Ok(self.layout.pack(millis as i64, 0, self.service_id))
+    }
+
+    // the highest bits of the id in its layout, i.e. the timestamp and what follows
+    // it, shifted down to a small integer, e.g. as a partition key, where the same
+    // bits keep ids of the same time window together, bits is clamped to the bits
+    // the layout uses
+    pub fn shard_prefix(&self, id: i64, bits: u32) -> u64 {
+        let id_bits = u64::BITS - (self.layout.max_id() as u64).leading_zeros();
+        let bits = bits.min(id_bits);
+        if bits == 0 {
+            return 0;
+        }
+        (id as u64 & self.layout.max_id() as u64) >> (id_bits - bits)
+    }
+
+    // position of the id's sequence within its millisecond, from 0.0 for the first
+    // id to 1.0 for the last one the layout allows, e.g. to plot how full every
+    // millisecond got, layouts without sequence bits always give 0.0
+    pub fn sequence_fraction(&self, id: i64) -> f64 {
+        let max_seq = self.layout.max_sequence();
+        if max_seq == 0 {
+            return 0.0;
+        }
+        self.layout.sequence_of(id) as f64 / max_seq as f64
+    }
+
+    // the timestamp field an id generated now would get, without generating one
+    // or touching the sequence, e.g. for a health check of the clock and epoch
+    pub fn current_timestamp_field(&self) -> Result<i64, SnowflakeError> {
+        let millis = self.elapsed()?.as_millis();
+        let max = self.layout.max_timestamp();
+        if millis > max as u128 {
+            return Err(SnowflakeError::TimestampOverflowError { millis, max });
+        }
+        Ok(millis as i64)
+    }
+
+    // inclusive bounds of every id that can be generated between start and end,
+    // useful to scan a time window in an index sorted by id
+    #[cfg(feature = "std")]
+    pub fn id_range_for(&self, start: SystemTime, end: SystemTime) -> (i64, i64) {
+        (
+            self.layout.pack(self.millis_at(start), 0, 0),
+            self.layout.pack(
+                self.millis_at(end),
+                self.layout.max_sequence(),
+                self.layout.max_service_id(),
+            ),
+        )
+    }
+
+    // the smallest id of the first second starting at or after t, ids generated in
+    // a second are at least its boundary id and less than the next second's one
+    #[cfg(feature = "std")]
+    pub fn second_boundary_id(&self, t: SystemTime) -> i64 {
+        let since_unix_epoch = t.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let mut seconds = since_unix_epoch.as_secs();
+        if since_unix_epoch.subsec_nanos() > 0 {
+            seconds += 1;
+        }
+        let boundary = UNIX_EPOCH + Duration::from_secs(seconds);
+        self.layout.pack(self.millis_at(boundary), 0, 0)
+    }
+
+    // milliseconds since epoch at the given time, clamped to the timestamp range
+    #[cfg(feature = "std")]
+    fn millis_at(&self, time: SystemTime) -> i64 {
+        time.duration_since(self.epoch()).map_or(0, |elapsed| {
+            elapsed.as_millis().min(self.layout.max_timestamp() as u128) as i64
+        })
+    }
+
+    fn next_seq(&mut self) -> u32 {
+        // wraps around the sequence range, so any offset still yields every value once
+        let seq = (self.seq_offset + self.seq) & self.layout.max_sequence();
+        self.warn_saturation(1);
+        self.seq += 1;
+        seq
+    }
+
+    // calls the saturation hook if the next n ids of the sequence take it to 90% of
+    // the ids a single millisecond can hold
+    fn warn_saturation(&self, n: u32) {
+        let threshold = ((self.layout.max_sequence() as u64 + 1) * 9 / 10) as u32;
+        if (self.seq..self.seq + n).contains(&threshold)
+            && !SATURATION_WARNED.swap(true, Ordering::Relaxed)
+        {
+            if let Some(hook) = self.saturation_hook {
+                hook();
+            }
+        }
+    }
+
+    fn reset_seq(&mut self) {
+        self.seq = 0;
+        self.seq_offset = if self.random_sequence_start {
+            random_u32() & self.layout.max_sequence()
+        } else {
+            0
+        };
+    }
+
+    // how long gen would wait for the clock if called now, if at all, so async code
+    // can do that waiting without blocking, errors and overflow are left to gen
+    #[cfg(feature = "tokio")]
+    fn pending_wait(&self) -> Result<Option<Duration>, SnowflakeError> {
+        let elapsed = self.elapsed()?;
+        let millis = elapsed.as_millis() as i64;
+        if millis > self.layout.max_timestamp() {
+            return Ok(None);
+        }
+        let behind = self.last_millis - millis;
+        let wait_for = if behind > 0
+            && !self.within_drift(behind)
+            && self
+                .max_backward_skew
+                .is_some_and(|max| Duration::from_millis(behind as u64) <= max)
+        {
+            self.last_millis
+        } else if behind >= 0
+            && self.seq > self.layout.max_sequence()
+            && !(self.last_millis < self.layout.max_timestamp() && self.within_drift(behind + 1))
+        {
+            self.last_millis + 1
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(Duration::from_millis(wait_for as u64) - elapsed))
+    }
+
+    fn wait_next_millis(&self) -> Result<i64, SnowflakeError> {
+        self.wait_for_millis(self.last_millis + 1)
+    }
+
+    // sleeps until the clock reaches min_millis, returning the millisecond it woke up in
+    fn wait_for_millis(&self, min_millis: i64) -> Result<i64, SnowflakeError> {
+        let spin_for = match self.wait_strategy {
+            WaitStrategy::Sleep => Duration::ZERO,
+            WaitStrategy::Spin => Duration::MAX,
+            WaitStrategy::SpinThenSleep { spin_for } => spin_for,
+        };
+        // Instant panics on wasm32-unknown-unknown, so it's only read to spin
+        let spin_start = (!spin_for.is_zero()).then(|| self.spin_start());
+        let mut spun = Duration::ZERO;
+        let mut slept = Duration::ZERO;
+        loop {
+            let (elapsed, millis) = self.get_time()?;
+            if millis >= min_millis {
+                return Ok(millis);
+            }
+            let waited = spun + slept;
+            if self.max_clock_stall.is_some_and(|max| waited >= max) {
+                return Err(SnowflakeError::ClockStalledError { waited });
+            }
+            if let Some(spin_start) = spin_start.filter(|_| spun < spin_for) {
+                core::hint::spin_loop();
+                spun = self.spun_since(spin_start);
+                continue;
+            }
+            let sleep_duration = Duration::from_millis(min_millis as u64) - elapsed;
+            self.clock.sleep(sleep_duration);
+            slept += sleep_duration;
+        }
+    }
+
+    // spinning is timed on an Instant, or without std on the clock
+    #[cfg(feature = "std")]
+    fn spin_start(&self) -> Instant {
+        Instant::now()
+    }
+
+    #[cfg(feature = "std")]
+    fn spun_since(&self, start: Instant) -> Duration {
+        start.elapsed()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn spin_start(&self) -> Duration {
+        self.clock.now()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn spun_since(&self, start: Duration) -> Duration {
+        self.clock.now().saturating_sub(start)
+    }
+
+    // time elapsed since epoch, along with it in whole milliseconds
+    fn get_time(&self) -> Result<(Duration, i64), SnowflakeError> {
+        let elapsed = self.elapsed()?;
+        Ok((elapsed, elapsed.as_millis() as i64))
+    }
+
+    fn elapsed(&self) -> Result<Duration, SnowflakeError> {
+        let now = self.clock.now();
+        now.checked_sub(self.epoch)
+            .ok_or_else(|| SnowflakeError::ClockBeforeEpochError {
+                behind: self.epoch - now,
+            })
+    }
+}
+
+impl<C: Clock + Clone> Snowflake<C> {
+    // a new generator for another service id with every setting of this one, most
+    // of all its epoch and layout, so the ids of both stay comparable, its clock is
+    // a clone of this one's, so clones of a MockClock drive both
+    pub fn sibling(&self, service_id: u16) -> Result<Self, SnowflakeError> {
+        SnowflakeBuilder {
+            clock: self.clock.clone(),
+            service_id,
+            epoch: Ok(self.epoch),
+            layout: self.layout,
+            random_sequence_start: self.random_sequence_start,
+            max_clock_stall: self.max_clock_stall,
+            saturation_hook: self.saturation_hook,
+            max_backward_skew: self.max_backward_skew,
+            overflow_policy: self.overflow_policy,
+            service_id_offset: self.service_id_offset,
+            wait_strategy: self.wait_strategy,
+            exhaustion_policy: self.exhaustion_policy,
+            // this generator was built with the same layout already
+            allow_short_lifespan: true,
+            last_id: None,
+            state: None,
+            #[cfg(feature = "tracing")]
+            trace_level: self.trace_level,
+            poison_policy: PoisonPolicy::default(),
+        }
+        .build()
+    }
+}
+
+// a one line summary of the configuration, e.g. for startup logs
+impl<C> core::fmt::Display for Snowflake<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "Snowflake(service_id={}, epoch={}, layout={})",
+            self.service_id + self.service_id_offset,
+            Rfc3339(self.epoch.as_millis() as i64),
+            self.layout
+        )
+    }
+}
+
+// formats milliseconds since UNIX_EPOCH as an rfc 3339 utc date, with
+// milliseconds only when there are any
+struct Rfc3339(i64);
+
+impl core::fmt::Display for Rfc3339 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let millis = self.0;
+        let (days, millis_of_day) = (millis.div_euclid(86_400_000), millis.rem_euclid(86_400_000));
+
+        // civil date from days since 1970-01-01, from howard hinnant's date algorithms
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        let seconds_of_day = millis_of_day / 1_000;
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+            seconds_of_day / 3_600,
+            seconds_of_day / 60 % 60,
+            seconds_of_day % 60
+        )?;
+        if millis_of_day % 1_000 != 0 {
+            write!(f, ".{:03}", millis_of_day % 1_000)?;
+        }
+        write!(f, "Z")
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SnowflakeConfig {
+    pub epoch: SystemTime,
+    pub service_id: u16,
+    pub layout: SnowflakeLayout,
+}
+
+pub struct SnowflakeBuilder<C = SystemClock> {
+    clock: C,
+    service_id: u16,
+    // since UNIX_EPOCH, or how long before it an epoch set as a SystemTime was
+    epoch: Result<Duration, Duration>,
+    layout: SnowflakeLayout,
+    random_sequence_start: bool,
+    max_clock_stall: Option<Duration>,
+    saturation_hook: Option<fn()>,
+    max_backward_skew: Option<Duration>,
+    overflow_policy: OverflowPolicy,
+    service_id_offset: u16,
+    wait_strategy: WaitStrategy,
+    allow_short_lifespan: bool,
+    exhaustion_policy: ExhaustionPolicy,
+    last_id: Option<i64>,
+    state: Option<SnowflakeState>,
+    #[cfg(feature = "tracing")]
+    trace_level: tracing::Level,
+    poison_policy: PoisonPolicy,
+}
+
+impl SnowflakeBuilder {
+    pub fn new(service_id: u16) -> Self {
+        Self {
+            clock: SystemClock,
+            service_id,
+            epoch: Ok(Duration::ZERO),
+            layout: SnowflakeLayout::DEFAULT,
+            random_sequence_start: false,
+            max_clock_stall: None,
+            saturation_hook: None,
+            max_backward_skew: None,
+            overflow_policy: OverflowPolicy::default(),
+            service_id_offset: 0,
+            wait_strategy: WaitStrategy::default(),
+            allow_short_lifespan: false,
+            exhaustion_policy: ExhaustionPolicy::default(),
+            last_id: None,
+            state: None,
+            #[cfg(feature = "tracing")]
+            trace_level: tracing::Level::TRACE,
+            poison_policy: PoisonPolicy::default(),
+        }
+    }
+}
+
+// the settings don't need a Clock, as without std SystemClock isn't one, and a
+// builder only gets the caller's clock along with them
+impl<C> SnowflakeBuilder<C> {
+    pub fn clock<D: Clock>(self, clock: D) -> SnowflakeBuilder<D> {
+        SnowflakeBuilder {
+            clock,
+            service_id: self.service_id,
+            epoch: self.epoch,
+            layout: self.layout,
+            random_sequence_start: self.random_sequence_start,
+            max_clock_stall: self.max_clock_stall,
+            saturation_hook: self.saturation_hook,
+            max_backward_skew: self.max_backward_skew,
+            overflow_policy: self.overflow_policy,
+            service_id_offset: self.service_id_offset,
+            wait_strategy: self.wait_strategy,
+            allow_short_lifespan: self.allow_short_lifespan,
+            exhaustion_policy: self.exhaustion_policy,
+            last_id: self.last_id,
+            state: self.state,
+            #[cfg(feature = "tracing")]
+            trace_level: self.trace_level,
+            poison_policy: self.poison_policy,
+        }
+    }
+
+    // epochs before UNIX_EPOCH are an EpochBeforeUnixEpochError once built
+    #[cfg(feature = "std")]
+    pub fn epoch(mut self, epoch: SystemTime) -> Self {
+        self.epoch = epoch
+            .duration_since(UNIX_EPOCH)
+            .map_err(|before| before.duration());
+        self
+    }
+
+    // epoch, as the time since UNIX_EPOCH, e.g. without std, where there's no
+    // SystemTime
+    pub fn epoch_since_unix_epoch(mut self, epoch: Duration) -> Self {
+        self.epoch = Ok(epoch);
+        self
+    }
+
+    pub fn layout(mut self, layout: SnowflakeLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    // start each millisecond's sequence at a random value instead of zero, so the
+    // sequence of an id doesn't reveal how many ids came before it in its millisecond,
+    // at the cost of ids within the same millisecond no longer sorting by creation order
+    pub fn random_sequence_start(mut self, enabled: bool) -> Self {
+        self.random_sequence_start = enabled;
+        self
+    }
+
+    // give up with a ClockStalledError when the sequence is exhausted and the clock
+    // hasn't moved to the next millisecond after sleeping this long, instead of
+    // waiting for it forever
+    pub fn max_clock_stall(mut self, max_clock_stall: Duration) -> Self {
+        self.max_clock_stall = Some(max_clock_stall);
+        self
+    }
+
+    // when the clock moves back behind the last id by at most this much, e.g. after an
+    // ntp adjustment, wait for it to catch up, and give up with a ClockWentBackwardError
+    // beyond it, so Duration::ZERO fails right away, without it ids keep using the
+    // timestamp of the last id until the clock catches up, waiting only once its
+    // sequence runs out
+    pub fn max_backward_skew(mut self, max_backward_skew: Duration) -> Self {
+        self.max_backward_skew = Some(max_backward_skew);
+        self
+    }
+
+    // called once per process, by the first generator whose sequence crosses 90% of a
+    // millisecond's capacity, as an early warning before ids have to wait for the clock
+    pub fn saturation_hook(mut self, hook: fn()) -> Self {
+        self.saturation_hook = Some(hook);
+        self
+    }
+
+    // see WaitStrategy
+    pub fn wait_strategy(mut self, wait_strategy: WaitStrategy) -> Self {
+        self.wait_strategy = wait_strategy;
+        self
+    }
+
+    // see ExhaustionPolicy
+    pub fn exhaustion_policy(mut self, exhaustion_policy: ExhaustionPolicy) -> Self {
+        self.exhaustion_policy = exhaustion_policy;
+        self
+    }
+
+    // see OverflowPolicy
+    pub fn overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    // number service ids from offset instead of zero, e.g. 1 for a registry whose
+    // services start at 1, every service id passed in or returned is then numbered
+    // from offset, while ids store it minus offset
+    pub fn service_id_offset(mut self, offset: u16) -> Self {
+        self.service_id_offset = offset;
+        self
+    }
+
+    // accepts layouts whose timestamp overflows less than MIN_LIFESPAN after the
+    // epoch, which build rejects otherwise as they are almost always a mistake
+    pub fn allow_short_lifespan(mut self) -> Self {
+        self.allow_short_lifespan = true;
+        self
+    }
+
+    // see Snowflake::resume_from
+    pub fn resume_from(mut self, last_id: i64) -> Self {
+        self.last_id = Some(last_id);
+        self
+    }
+
+    // see SnowflakeState
+    pub fn restore(mut self, state: SnowflakeState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    // level of the event emitted for every generated id, trace by default
+    #[cfg(feature = "tracing")]
+    pub fn trace_level(mut self, level: tracing::Level) -> Self {
+        self.trace_level = level;
+        self
+    }
+
+    // only applies to generators created with build_concurrent
+    pub fn poison_policy(mut self, poison_policy: PoisonPolicy) -> Self {
+        self.poison_policy = poison_policy;
+        self
+    }
+}
+
+impl<C: Clock> SnowflakeBuilder<C> {
+    #[cfg(feature = "std")]
+    pub fn build_concurrent(self) -> Result<ConcurrentSnowflake<C>, SnowflakeError> {
+        let poison_policy = self.poison_policy;
+        Ok(ConcurrentSnowflake {
+            inner: Arc::new(Mutex::new(self.build()?)),
+            poison_policy,
+            paused: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn build(self) -> Result<Snowflake<C>, SnowflakeError> {
+        let lifespan = Duration::from_millis(self.layout.max_timestamp() as u64);
+        if lifespan < MIN_LIFESPAN && !self.allow_short_lifespan {
+            return Err(SnowflakeError::ShortLifespanError {
+                timestamp_bits: self.layout.timestamp_bits(),
+                lifespan,
+            });
+        }
+        let epoch = self
+            .epoch
+            .map_err(|before| SnowflakeError::EpochBeforeUnixEpochError { before })?;
+        let service_id = service_id_field(self.service_id, self.service_id_offset, self.layout)?;
+        let mut snowflake = Snowflake {
+            clock: self.clock,
+            epoch,
+            layout: self.layout,
+            service_id,
+            last_millis: 0,
+            seq: 0,
+            seq_offset: 0,
+            generated: 0,
+            sequence_rollovers: 0,
+            millis_waited: 0,
+            clock_regressions: 0,
+            random_sequence_start: self.random_sequence_start,
+            max_clock_stall: self.max_clock_stall,
+            saturation_hook: self.saturation_hook,
+            max_backward_skew: self.max_backward_skew,
+            overflow_policy: self.overflow_policy,
+            service_id_offset: self.service_id_offset,
+            wait_strategy: self.wait_strategy,
+            exhaustion_policy: self.exhaustion_policy,
+            #[cfg(feature = "tracing")]
+            trace_level: self.trace_level,
+            _registration: Registration::new(service_id),
+        };
+        snowflake.reset_seq();
+        if let Some(last_id) = self.last_id {
+            if last_id < 0 {
+                return Err(SnowflakeError::NegativeIdError { id: last_id });
+            }
+            let (millis, seq, found) = self.layout.decode(last_id);
+            if found != service_id {
+                return Err(SnowflakeError::ServiceIdMismatchError {
+                    expected: self.service_id,
+                    found: found.saturating_add(self.service_id_offset),
+                });
+            }
+            snowflake.last_millis = millis;
+            if self.random_sequence_start {
+                // the sequence of last_id may have wrapped around from any random start,
+                // so none of its millisecond is left, and the next id waits for the next
+                snowflake.seq = self.layout.max_sequence() + 1;
+            } else {
+                // carry on right after last_id, an exhausted sequence waits for the next
+                // millisecond
+                snowflake.seq_offset = 0;
+                snowflake.seq = seq + 1;
+            }
+        }
+        if let Some(state) = self.state {
+            if state.service_id != service_id {
+                return Err(SnowflakeError::ServiceIdMismatchError {
+                    expected: self.service_id,
+                    found: state.service_id.saturating_add(self.service_id_offset),
+                });
+            }
+            let max_timestamp = self.layout.max_timestamp();
+            if state.last_millis > max_timestamp as u64 {
+                return Err(SnowflakeError::InvalidTimestampError {
+                    millis: state.last_millis.min(i64::MAX as u64) as i64,
+                    max: max_timestamp,
+                });
+            }
+            // a sequence one past its maximum is exhausted, which is a valid state
+            let max_seq = self.layout.max_sequence();
+            for seq in [state.seq.saturating_sub(1), state.seq_offset] {
+                if seq > max_seq {
+                    return Err(SnowflakeError::InvalidSequenceError { seq, max: max_seq });
+                }
+            }
+            snowflake.last_millis = state.last_millis as i64;
+            snowflake.seq = state.seq;
+            snowflake.seq_offset = state.seq_offset;
+        }
+        Ok(snowflake)
+    }
+}
+
+// the first constraint of a deployment that Snowflake::validate_for found unmet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutProblem {
+    ThroughputTooHighError {
+        expected_qps: u64,
+        max_qps: u64,
+    },
+    TooManyServicesError {
+        service_count: u16,
+        max_services: u32,
+    },
+    #[cfg(feature = "std")]
+    TimestampOverflowError {
+        until: SystemTime,
+        overflows_at: SystemTime,
+    },
+}
+
+impl core::fmt::Display for LayoutProblem {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::ThroughputTooHighError {
+                expected_qps,
+                max_qps,
+            } => write!(
+                f,
+                "{expected_qps} ids per second exceed the {max_qps} a generator can generate"
+            ),
+            Self::TooManyServicesError {
+                service_count,
+                max_services,
+            } => {
+                // the fewest bits with room for a service id per service
+                let bits_needed = u16::BITS - service_count.saturating_sub(1).leading_zeros();
+                write!(
+                    f,
+                    "{service_count} services need more than the {max_services} available service ids, a layout with {bits_needed} service id bits would fit them"
+                )
+            }
+            #[cfg(feature = "std")]
+            Self::TimestampOverflowError {
+                until,
+                overflows_at,
+            } => write!(
+                f,
+                "timestamps overflow at {overflows_at:?}, before {until:?}"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LayoutProblem {}
+
+// the value stored in ids for a service id numbered from offset
+fn service_id_field(
+    service_id: u16,
+    offset: u16,
+    layout: SnowflakeLayout,
+) -> Result<u16, SnowflakeError> {
+    let field = service_id
+        .checked_sub(offset)
+        .ok_or(SnowflakeError::ServiceIdBelowOffsetError { service_id, offset })?;
+    if field > layout.max_service_id() {
+        return Err(SnowflakeError::InvalidServiceIdError {
+            service_id,
+            max: layout.max_service_id().saturating_add(offset),
+        });
+    }
+    Ok(field)
+}
+
+// 64 bit fnv-1a, stable across processes and platforms unlike the std hashers
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// every RandomState is seeded with fresh keys, so hashing nothing yields a random value
+#[cfg(feature = "std")]
+fn random_u32() -> u32 {
+    RandomState::new().build_hasher().finish() as u32
+}
+
+// without std there's nothing to seed from, so every process goes through the
+// same splitmix32 sequence, which still keeps sequences from counting up from 0
+#[cfg(not(feature = "std"))]
+fn random_u32() -> u32 {
+    use core::sync::atomic::AtomicU32;
+
+    static STATE: AtomicU32 = AtomicU32::new(0);
+    let mut z = STATE
+        .fetch_add(0x9e3779b9, Ordering::Relaxed)
+        .wrapping_add(0x9e3779b9);
+    z = (z ^ (z >> 16)).wrapping_mul(0x21f0aaad);
+    z = (z ^ (z >> 15)).wrapping_mul(0x735a2d97);
+    z ^ (z >> 15)
+}
+
+#[derive(Debug)]
+#[must_use]
+pub enum SnowflakeError {
+    InvalidServiceIdError {
+        service_id: u16,
+        max: u16,
+    },
+    InvalidLayoutError {
+        timestamp_bits: u32,
+        sequence_bits: u32,
+        service_id_bits: u32,
+    },
+    EmptyServiceIdsError,
+    DuplicateServiceIdError {
+        service_id: u16,
+    },
+    NegativeIdError {
+        id: i64,
+    },
+    ClockStalledError {
+        waited: Duration,
+    },
+    ServiceIdMismatchError {
+        expected: u16,
+        found: u16,
+    },
+    ClockBeforeEpochError {
+        behind: Duration,
+    },
+    TimestampOverflowError {
+        millis: u128,
+        max: i64,
+    },
+    ClockWentBackwardError {
+        behind: Duration,
+    },
+    InvalidIdStringError {
+        input: String,
+    },
+    InvalidEpochStringError {
+        input: String,
+    },
+    #[cfg(feature = "std")]
+    EpochInFutureError {
+        epoch: SystemTime,
+    },
+    InvalidNamespaceBitsError {
+        namespace_bits: u32,
+        sequence_bits: u32,
+    },
+    InvalidNamespaceError {
+        namespace: u8,
+        max: u8,
+    },
+    ServiceIdBelowOffsetError {
+        service_id: u16,
+        offset: u16,
+    },
+    InvalidNonceBitsError {
+        nonce_bits: u32,
+        sequence_bits: u32,
+    },
+    InvalidTimestampError {
+        millis: i64,
+        max: i64,
+    },
+    InvalidSequenceError {
+        seq: u32,
+        max: u32,
+    },
+    UnknownServiceIdError {
+        service_id: u16,
+    },
+    UnexpectedServiceIdError {
+        service_id: u16,
+        allowed: Vec<u16>,
+    },
+    ShortLifespanError {
+        timestamp_bits: u32,
+        lifespan: Duration,
+    },
+    InvalidMnemonicError {
+        input: String,
+    },
+    PossibleDuplicateError {
+        id: i64,
+    },
+    InvalidStateJsonError {
+        input: String,
+    },
+    NonContiguousLayoutError {
+        layout: SnowflakeLayout,
+    },
+    RangeSpansMillisecondsError {
+        n: u32,
+        remaining: u32,
+    },
+    InvalidEncodedIdError {
+        input: String,
+        encoding: &'static str,
+    },
+    ServiceIdSourceError {
+        source: String,
+    },
+    DerivedServiceIdError {
+        source: String,
+        value: u64,
+        max: u16,
+    },
+    EpochMovedForwardError {
+        service_id: u16,
+        by: Duration,
+    },
+    EpochBeforeUnixEpochError {
+        before: Duration,
+    },
+    EmptyBufferError,
+}
+
+impl SnowflakeError {
+    // whether the same call may succeed later without changing anything, i.e. the
+    // clock stalled or went back further than allowed but may still catch up
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::ClockStalledError { .. } | Self::ClockWentBackwardError { .. }
+        )
+    }
+}
+
+impl core::fmt::Display for SnowflakeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::InvalidServiceIdError { service_id, max } => {
+                write!(f, "service id {service_id} exceeds maximum {max}")
+            }
+            Self::InvalidLayoutError {
+                timestamp_bits,
+                sequence_bits,
+                service_id_bits,
+            } => write!(
+                f,
+                "layout of {timestamp_bits}/{sequence_bits}/{service_id_bits} bits is invalid, \
+                 it needs at least 1 timestamp bit, at most 31 sequence bits, \
+                 at most 16 service id bits and at most 63 bits in total"
+            ),
+            Self::EmptyServiceIdsError => write!(f, "at least one service id is required"),
+            Self::DuplicateServiceIdError { service_id } => {
+                write!(f, "service id {service_id} is used more than once")
+            }
+            Self::NegativeIdError { id } => {
+                write!(f, "id {id} is negative, the signing bit must not be set")
+            }
+            Self::ClockStalledError { waited } => write!(
+                f,
+                "clock did not advance to the next millisecond after waiting {waited:?}"
+            ),
+            Self::ServiceIdMismatchError { expected, found } => {
+                write!(
+                    f,
+                    "expected service id {expected} but id has service id {found}"
+                )
+            }
+            Self::ClockBeforeEpochError { behind } => {
+                write!(f, "clock is {behind:?} behind the epoch")
+            }
+            Self::TimestampOverflowError { millis, max } => write!(
+                f,
+                "{millis}ms since the epoch exceeds the maximum timestamp {max}"
+            ),
+            Self::ClockWentBackwardError { behind } => write!(
+                f,
+                "clock went back {behind:?} behind the last id, more than the allowed skew"
+            ),
+            Self::InvalidIdStringError { input } => write!(f, "{input:?} is not a valid id"),
+            Self::InvalidEpochStringError { input } => {
+                write!(f, "{input:?} is not a valid rfc 3339 date")
+            }
+            #[cfg(feature = "std")]
+            Self::EpochInFutureError { epoch } => write!(f, "epoch {epoch:?} is in the future"),
+            Self::InvalidNamespaceBitsError {
+                namespace_bits,
+                sequence_bits,
+            } => write!(
+                f,
+                "can't take {namespace_bits} namespace bits out of {sequence_bits} sequence bits, \
+                 at most 8 namespace bits are supported"
+            ),
+            Self::InvalidNamespaceError { namespace, max } => {
+                write!(f, "namespace {namespace} exceeds maximum {max}")
+            }
+            Self::ServiceIdBelowOffsetError { service_id, offset } => write!(
+                f,
+                "service id {service_id} is below the first service id {offset}"
+            ),
+            Self::InvalidNonceBitsError {
+                nonce_bits,
+                sequence_bits,
+            } => write!(
+                f,
+                "can't take {nonce_bits} nonce bits out of {sequence_bits} sequence bits"
+            ),
+            Self::InvalidTimestampError { millis, max } => {
+                write!(f, "timestamp {millis} is not between 0 and {max}")
+            }
+            Self::InvalidSequenceError { seq, max } => {
+                write!(f, "sequence {seq} exceeds maximum {max}")
+            }
+            Self::UnknownServiceIdError { service_id } => {
+                write!(f, "service id {service_id} is not generated here")
+            }
+            Self::UnexpectedServiceIdError {
+                service_id,
+                allowed,
+            } => write!(f, "service id {service_id} is not one of {allowed:?}"),
+            Self::ShortLifespanError {
+                timestamp_bits,
+                lifespan,
+            } => write!(
+                f,
+                "{timestamp_bits} timestamp bits overflow {lifespan:?} after the epoch, which is less than {MIN_LIFESPAN:?}"
+            ),
+            Self::InvalidMnemonicError { input } => {
+                write!(f, "{input:?} is not a mnemonic of an id")
+            }
+            Self::PossibleDuplicateError { id } => {
+                write!(f, "id {id} may have been generated before")
+            }
+            Self::InvalidStateJsonError { input } => {
+                write!(f, "{input:?} is not the json of a generator state")
+            }
+            Self::NonContiguousLayoutError { layout } => write!(
+                f,
+                "layout {layout} doesn't keep the whole sequence in the lowest bits, so its ids are never consecutive"
+            ),
+            Self::RangeSpansMillisecondsError { n, remaining } => write!(
+                f,
+                "{n} consecutive ids don't fit in the {remaining} left in the millisecond"
+            ),
+            Self::InvalidEncodedIdError { input, encoding } => {
+                write!(f, "{input:?} is not a {encoding} encoded id")
+            }
+            Self::ServiceIdSourceError { source } => {
+                write!(f, "couldn't derive a service id from {source}")
+            }
+            Self::DerivedServiceIdError { source, value, max } => write!(
+                f,
+                "service id {value} derived from {source} is larger than the layout's max {max}"
+            ),
+            Self::EpochMovedForwardError { service_id, by } => write!(
+                f,
+                "moving the epoch of service id {service_id} forward by {by:?} would repeat timestamps of ids it generated"
+            ),
+            Self::EpochBeforeUnixEpochError { before } => write!(
+                f,
+                "epoch is {before:?} before UNIX_EPOCH, the earliest epoch there is"
+            ),
+            Self::EmptyBufferError => write!(f, "a buffer of at least one id is required"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SnowflakeError {}
+
+#[cfg(test)]
+// the uniqueness tests collect their filtered ids instead of retaining them
+#[allow(clippy::manual_retain)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+    #[cfg(feature = "std")]
+    use std::collections::HashSet;
+    #[cfg(feature = "std")]
+    use std::sync::atomic::AtomicU64;
+    use std::sync::{Arc, Mutex};
+
+    const NUM_IDS: u64 = 1_000_000;
+
+    proptest! {
+        #[test]
+        fn test_decode_is_the_inverse_of_pack(
+            millis in 0..=MAX_44_BITS,
+            seq in 0..=MAX_17_BITS,
+            service_id in 0..=MAX_2_BITS,
+        ) {
+            prop_assert_eq!(decode(pack(millis, seq, service_id)), (millis, seq, service_id));
+        }
+
+        #[test]
+        fn test_pack_is_the_inverse_of_decode(id in 0..=i64::MAX) {
+            let (millis, seq, service_id) = decode(id);
+            prop_assert_eq!(pack(millis, seq, service_id), id);
+        }
+    }
+
+    #[test]
+    fn test_pack_is_usable_in_const_context() {
+        const ID: i64 = pack(1, 2, 3);
+        assert_eq!(ID, 1 << 19 | 2 << 2 | 3);
+    }
+
+    #[test]
+    fn test_pack_masks_fields_to_their_width() {
+        assert_eq!(pack(MAX_44_BITS + 1, 0, 0), 0);
+        assert_eq!(pack(0, MAX_17_BITS + 1, 0), 0);
+        assert_eq!(pack(0, 0, MAX_2_BITS + 1), 0);
+        assert_eq!(pack(MAX_44_BITS, MAX_17_BITS, MAX_2_BITS), i64::MAX);
+    }
+
+    #[test]
+    fn test_field_accessors_extract_single_fields() {
+        let id = pack(123_456, 789, 2);
+        assert_eq!(timestamp_bits_of(id), 123_456);
+        assert_eq!(sequence_of(id), 789);
+        assert_eq!(service_id_of(id), 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_reads_generated_service_id() {
+        let mut snowflake = Snowflake::new(2).unwrap();
+        let (_, _, service_id) = decode(snowflake.gen().unwrap());
+        assert_eq!(service_id, 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_parts_and_decode_recover_what_gen_packed() {
+        let clock = MockClock::new(Duration::from_millis(5_000));
+        let epoch = UNIX_EPOCH + Duration::from_secs(1);
+        let mut snowflake = Snowflake::builder(9)
+            .layout(SnowflakeLayout::DISCORD)
+            .epoch(epoch)
+            .service_id_offset(8)
+            .clock(clock)
+            .build()
+            .unwrap();
+        snowflake.gen().unwrap();
+        let id = snowflake.gen().unwrap();
+        assert_eq!(
+            snowflake.parts(id).unwrap(),
+            SnowflakeParts {
+                timestamp_millis: 4_000,
+                seq: 1,
+                service_id: 9
+            }
+        );
+        let decoded = snowflake.decode(id).unwrap();
+        assert_eq!(decoded.timestamp, UNIX_EPOCH + Duration::from_millis(5_000));
+        assert_eq!((decoded.sequence, decoded.service_id), (1, 9));
+        assert!(matches!(
+            snowflake.parts(-id),
+            Err(SnowflakeError::NegativeIdError { .. })
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_remaining_timestamp_range_counts_down_to_overflow() {
+        const YEAR: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+        let snowflake = Snowflake::new(0).unwrap();
+        assert!(snowflake.remaining_timestamp_range() > 500 * YEAR);
+
+        // the epoch can't go back past UNIX_EPOCH, so the clock moves ahead instead
+        let minute = Duration::from_secs(60);
+        let overflow = Duration::from_millis(MAX_44_BITS as u64);
+        let clock = MockClock::new(overflow - minute);
+        let snowflake = Snowflake::builder(0).clock(clock.clone()).build().unwrap();
+        assert_eq!(snowflake.remaining_timestamp_range(), minute);
+
+        clock.set(overflow + minute);
+        assert_eq!(snowflake.remaining_timestamp_range(), Duration::ZERO);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_sequence_covers_whole_range_once_per_millisecond() {
+        let mut snowflake = Snowflake::new(0).unwrap();
+        let mut seqs: Vec<u32> = (0..=MAX_17_BITS).map(|_| snowflake.next_seq()).collect();
+        assert_eq!(seqs[0], 0);
+        assert!(snowflake.seq > MAX_17_BITS);
+        seqs.sort();
+        seqs.dedup();
+        assert_eq!(seqs.len(), MAX_17_BITS as usize + 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_random_sequence_start_covers_whole_range_once_per_millisecond() {
+        let mut snowflake = Snowflake::builder(0)
+            .random_sequence_start(true)
+            .build()
+            .unwrap();
+        let mut offsets = Vec::new();
+        for _ in 0..8 {
+            snowflake.reset_seq();
+            offsets.push(snowflake.seq_offset);
+            let mut seqs: Vec<u32> = (0..=MAX_17_BITS).map(|_| snowflake.next_seq()).collect();
+            assert_eq!(seqs[0], snowflake.seq_offset);
+            assert!(snowflake.seq > MAX_17_BITS);
+            seqs.sort();
+            seqs.dedup();
+            assert_eq!(seqs.len(), MAX_17_BITS as usize + 1);
+        }
+        assert!(offsets.iter().any(|offset| *offset != 0));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_random_sequence_start_creates_unique_positive_ids() {
+        let mut snowflake = Snowflake::builder(0)
+            .random_sequence_start(true)
+            .build()
+            .unwrap();
+        let mut ids: Vec<i64> = (0..NUM_IDS).map(|_| snowflake.gen().unwrap()).collect();
+        ids.sort();
+        ids.dedup();
+        ids.retain(|id| *id > 0);
+        assert_eq!(ids.len(), NUM_IDS as usize);
+    }
+
+    #[test]
+    fn test_exhausted_sequence_waits_for_next_millisecond() {
+        let clock = MockClock::new(Duration::from_micros(1_000_250));
+        let mut snowflake = Snowflake::builder(0).clock(clock.clone()).build().unwrap();
+        for _ in 0..=MAX_17_BITS {
+            assert_eq!(timestamp_bits_of(snowflake.gen().unwrap()), 1_000);
+        }
+        let id = snowflake.gen().unwrap();
+        assert_eq!(decode(id), (1_001, 0, 0));
+        assert_eq!(clock.now(), Duration::from_millis(1_001));
+        assert!(sequence_saturation_warned());
+    }
+
+    // the kind of clock a caller without std supplies, e.g. over a hardware timer,
+    // whose wait just moves on to the next tick
+    struct TickClock {
+        millis: core::cell::Cell<u64>,
+    }
+
+    impl Clock for TickClock {
+        fn now(&self) -> Duration {
+            Duration::from_millis(self.millis.get())
+        }
+
+        fn sleep(&self, _duration: Duration) {
+            self.millis.set(self.millis.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_generates_with_a_caller_supplied_clock_and_epoch() {
+        let clock = TickClock {
+            millis: core::cell::Cell::new(5_000),
+        };
+        let mut snowflake = Snowflake::builder(1)
+            .epoch_since_unix_epoch(Duration::from_millis(1_000))
+            .layout(SnowflakeLayout::new(44, 1, 2).unwrap())
+            .clock(clock)
+            .build()
+            .unwrap();
+        assert_eq!(
+            snowflake.epoch_since_unix_epoch(),
+            Duration::from_millis(1_000)
+        );
+        let layout = snowflake.layout();
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (4_000, 0, 1));
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (4_000, 1, 1));
+        // the exhausted sequence waits on the clock
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (4_001, 0, 1));
+
+        let mut snowflake = Snowflake::builder(1)
+            .epoch_since_unix_epoch(Duration::from_millis(10_000))
+            .clock(TickClock {
+                millis: core::cell::Cell::new(5_000),
+            })
+            .build()
+            .unwrap();
+        assert!(matches!(
+            snowflake.gen(),
+            Err(SnowflakeError::ClockBeforeEpochError { behind }) if behind == Duration::from_secs(5)
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_epoch_before_unix_epoch_is_an_error() {
+        assert!(matches!(
+            Snowflake::with_epoch(0, UNIX_EPOCH - Duration::from_secs(1)),
+            Err(SnowflakeError::EpochBeforeUnixEpochError { before })
+                if before == Duration::from_secs(1)
+        ));
+        let epoch = UNIX_EPOCH + Duration::from_millis(1_500);
+        let snowflake = Snowflake::with_epoch(0, epoch).unwrap();
+        assert_eq!(snowflake.epoch(), epoch);
+        assert_eq!(
+            snowflake.epoch_since_unix_epoch(),
+            Duration::from_millis(1_500)
+        );
+    }
+
+    #[test]
+    fn test_zero_clock_stall_gives_up_without_sleeping() {
+        // how wasm_builder waits, where sleeping can't block
+        let clock = SleepRecordingClock {
+            clock: MockClock::new(Duration::from_millis(1_000)),
+            sleeps: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut snowflake = Snowflake::builder(0)
+            .clock(clock.clone())
+            .layout(SnowflakeLayout::new(44, 0, 2).unwrap())
+            .max_clock_stall(Duration::ZERO)
+            .build()
+            .unwrap();
+        snowflake.gen().unwrap();
+        let error = snowflake.gen().unwrap_err();
+        assert!(matches!(error, SnowflakeError::ClockStalledError { waited } if waited.is_zero()));
+        assert!(error.is_transient());
+        assert!(clock.sleeps.lock().unwrap().is_empty());
+
+        clock.clock.advance(Duration::from_millis(1));
+        assert_eq!(
+            snowflake
+                .layout()
+                .timestamp_bits_of(snowflake.gen().unwrap()),
+            1_001
+        );
+    }
+
+    #[test]
+    fn test_borrowing_timestamps_keeps_ids_unique_within_the_drift() {
+        // 4 ids per millisecond, on a clock that only moves when told to
+        let clock = MockClock::frozen(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1)
+            .layout(SnowflakeLayout::new(44, 2, 2).unwrap())
+            .clock(clock.clone())
+            .exhaustion_policy(ExhaustionPolicy::BorrowTimestamp {
+                max_drift: Duration::from_millis(10),
+            })
+            .max_backward_skew(Duration::ZERO)
+            .max_clock_stall(Duration::from_millis(1))
+            .build()
+            .unwrap();
+        let layout = snowflake.layout();
+        let ids: Vec<i64> = (0..44).map(|_| snowflake.gen().unwrap()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        let millis: Vec<i64> = ids.iter().map(|id| layout.timestamp_bits_of(*id)).collect();
+        assert_eq!(millis[0], 1_000);
+        assert_eq!(millis[43], 1_010);
+
+        // borrowing an 11th millisecond would drift too far, so it waits
+        assert!(matches!(
+            snowflake.gen(),
+            Err(SnowflakeError::ClockStalledError { .. })
+        ));
+
+        // the clock catching up part of the way lets it borrow again from there
+        clock.set(Duration::from_millis(1_005));
+        let id = snowflake.gen().unwrap();
+        assert_eq!(layout.decode(id), (1_011, 0, 1));
+        assert!(id > ids[43]);
+    }
+
+    #[test]
+    fn test_advance_to_next_millis_separates_ids_into_milliseconds() {
+        let clock = MockClock::new(Duration::from_micros(1_000_400));
+        let mut snowflake = Snowflake::builder(0).clock(clock.clone()).build().unwrap();
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_000, 0, 0));
+        snowflake.advance_to_next_millis().unwrap();
+        assert_eq!(clock.now(), Duration::from_millis(1_001));
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_001, 0, 0));
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_001, 1, 0));
+
+        // the clock already being past the last id doesn't wait
+        clock.advance(Duration::from_millis(5));
+        snowflake.advance_to_next_millis().unwrap();
+        assert_eq!(clock.now(), Duration::from_millis(1_006));
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_006, 0, 0));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_advance_to_next_millis_with_system_clock() {
+        let mut snowflake = Snowflake::new(0).unwrap();
+        let before = snowflake.gen().unwrap();
+        snowflake.advance_to_next_millis().unwrap();
+        let after = snowflake.gen().unwrap();
+        assert!(timestamp_bits_of(after) > timestamp_bits_of(before));
+        assert_eq!(sequence_of(after), 0);
+    }
+
+    #[test]
+    fn test_namespaces_interleave_uniquely() {
+        let layout = SnowflakeLayout::DEFAULT.with_namespace_bits(4).unwrap();
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(2)
+            .layout(layout)
+            .clock(clock)
+            .build()
+            .unwrap();
+        let mut ids: Vec<i64> = (0..100_000)
+            .map(|i| {
+                let namespace = (i % 3) as u8 * 7;
+                let id = snowflake.gen_for_namespace(namespace).unwrap();
+                assert_eq!(layout.namespace_of(id), namespace);
+                assert_eq!(layout.service_id_of(id), 2);
+                id
+            })
+            .collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 100_000);
+
+        assert!(matches!(
+            snowflake.gen_for_namespace(16),
+            Err(SnowflakeError::InvalidNamespaceError {
+                namespace: 16,
+                max: 15
+            })
+        ));
+    }
+
+    #[test]
+    fn test_ids_of_a_key_always_get_its_shard() {
+        let layout = SnowflakeLayout::new(41, 12, 10).unwrap();
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(0)
+            .layout(layout)
+            .clock(clock)
+            .build()
+            .unwrap();
+        let keys: Vec<String> = (0..20).map(|i| format!("user-{i}")).collect();
+        let mut ids = Vec::new();
+        let mut shards = Vec::new();
+        for _ in 0..500 {
+            for key in &keys {
+                ids.push(snowflake.gen_for_key(key.as_bytes()).unwrap());
+            }
+            let round: Vec<u16> = ids[ids.len() - keys.len()..]
+                .iter()
+                .map(|id| layout.service_id_of(*id))
+                .collect();
+            if shards.is_empty() {
+                shards = round;
+            } else {
+                assert_eq!(round, shards);
+            }
+        }
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a(b"user-0") % 1_024, shards[0] as u64);
+        shards.sort();
+        shards.dedup();
+        assert!(shards.len() > 10);
+
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 500 * keys.len());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_gen_range_reserves_consecutive_ids() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let layout = SnowflakeLayout::DISCORD;
+        let mut snowflake = Snowflake::builder(7)
+            .layout(layout)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let range = snowflake.gen_range(1_000).unwrap();
+        assert_eq!(range.end - range.start, 1_000);
+        assert_eq!(layout.decode(range.start), (1_000, 0, 7));
+        assert_eq!(layout.decode(range.end - 1), (1_000, 999, 7));
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (1_000, 1_000, 7));
+        assert_eq!(snowflake.gen_range(0).unwrap(), 0..0);
+
+        // 3095 ids are left in the millisecond
+        assert_eq!(
+            snowflake.gen_range(3_095).unwrap().end,
+            layout.pack(1_000, 4_095, 7) + 1
+        );
+        // the exhausted millisecond is left for the next one
+        let next = snowflake.gen_range(2).unwrap();
+        assert_eq!(layout.decode(next.start), (1_001, 0, 7));
+        assert!(matches!(
+            snowflake.gen_range(4_095),
+            Err(SnowflakeError::RangeSpansMillisecondsError {
+                n: 4_095,
+                remaining: 4_094
+            })
+        ));
+        // the failed call used up seq 2
+        let last = snowflake.gen_range(4_093).unwrap();
+        assert_eq!(layout.decode(last.start), (1_001, 3, 7));
+        assert_eq!(layout.decode(last.end - 1), (1_001, 4_095, 7));
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (1_002, 0, 7));
+
+        let mut default = Snowflake::new(0).unwrap();
+        assert!(matches!(
+            default.gen_range(2),
+            Err(SnowflakeError::NonContiguousLayoutError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_gen_range_refuses_namespace_layouts() {
+        // its ids are still consecutive, but all of the block would be in namespace 0
+        let layout = SnowflakeLayout::DISCORD.with_namespace_bits(2).unwrap();
+        let mut snowflake = Snowflake::builder(7)
+            .layout(layout)
+            .clock(MockClock::new(Duration::from_millis(1_000)))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            snowflake.gen_range(2),
+            Err(SnowflakeError::NonContiguousLayoutError { layout: found }) if found == layout
+        ));
+    }
+
+    #[test]
+    fn test_batch_boundaries_mark_new_milliseconds() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(0)
+            .layout(SnowflakeLayout::new(44, 3, 2).unwrap())
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        snowflake.gen().unwrap();
+
+        // 7 ids left in the first millisecond, then 8 per millisecond
+        let (ids, boundaries) = snowflake.gen_batch_with_boundaries(20).unwrap();
+        assert_eq!(boundaries, [7, 15]);
+        let layout = snowflake.layout();
+        for (i, id) in ids.iter().enumerate() {
+            let millis = 1_000 + boundaries.iter().filter(|b| **b <= i).count() as i64;
+            assert_eq!(layout.timestamp_bits_of(*id), millis);
+        }
+        assert_eq!(clock.now(), Duration::from_millis(1_002));
+
+        let (ids, boundaries) = snowflake.gen_batch_with_boundaries(3).unwrap();
+        assert_eq!((ids.len(), boundaries.len()), (3, 0));
+    }
+
+    #[test]
+    fn test_append_n_grows_a_reused_buffer() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1).clock(clock.clone()).build().unwrap();
+        let mut ids = vec![-1];
+        snowflake.append_n(&mut ids, 3).unwrap();
+        clock.advance(Duration::from_millis(1));
+        snowflake.append_n(&mut ids, 2).unwrap();
+        assert_eq!(
+            ids,
+            [
+                -1,
+                pack(1_000, 0, 1),
+                pack(1_000, 1, 1),
+                pack(1_000, 2, 1),
+                pack(1_001, 0, 1),
+                pack(1_001, 1, 1)
+            ]
+        );
+
+        ids.clear();
+        snowflake.append_n(&mut ids, NUM_IDS as usize).unwrap();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    // a MockClock that counts how often it is read
+    #[cfg(feature = "std")]
+    #[derive(Clone)]
+    struct ReadCountingClock {
+        clock: MockClock,
+        reads: Arc<AtomicU64>,
+    }
+
+    #[cfg(feature = "std")]
+    impl Clock for ReadCountingClock {
+        fn now(&self) -> Duration {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.clock.now()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.clock.sleep(duration);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_gen_many_reads_the_clock_once_per_millisecond() {
+        let clock = ReadCountingClock {
+            clock: MockClock::new(Duration::from_millis(1_000)),
+            reads: Arc::new(AtomicU64::new(0)),
+        };
+        let layout = SnowflakeLayout::new(44, 12, 7).unwrap();
+        let mut snowflake = Snowflake::builder(1)
+            .clock(clock.clone())
+            .layout(layout)
+            .build()
+            .unwrap();
+        let ids = snowflake.gen_many(10_000).unwrap();
+        assert_eq!(ids.len(), 10_000);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(layout.decode(ids[4_095]), (1_000, 4_095, 1));
+        assert_eq!(layout.decode(ids[4_096]), (1_001, 0, 1));
+        assert_eq!(layout.decode(ids[9_999]), (1_002, 1_807, 1));
+        // a read for the first id, then for each of the two exhausted milliseconds
+        // one finding it exhausted and two around the sleep waiting for the next
+        assert_eq!(clock.reads.load(Ordering::Relaxed), 7);
+
+        let mut concurrent = Snowflake::builder(1)
+            .clock(clock.clone())
+            .layout(layout)
+            .build_concurrent()
+            .unwrap();
+        let ids = concurrent.gen_many(5_000).unwrap();
+        assert_eq!(layout.decode(ids[0]), (1_002, 0, 1));
+        concurrent.pause();
+        assert!(matches!(
+            concurrent.gen_many(1),
+            Err(ConcurrentSnowflakeError::PausedError)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_write_ids_frames_every_id() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1).clock(clock).build().unwrap();
+        let mut text = Vec::new();
+        snowflake
+            .write_ids(&mut text, 3, IdFormat::Decimal)
+            .unwrap();
+        let expected = format!(
+            "{}\n{}\n{}\n",
+            pack(1_000, 0, 1),
+            pack(1_000, 1, 1),
+            pack(1_000, 2, 1)
+        );
+        assert_eq!(String::from_utf8(text).unwrap(), expected);
+
+        let mut binary = Vec::new();
+        snowflake
+            .write_ids(&mut binary, 2, IdFormat::BigEndian)
+            .unwrap();
+        assert_eq!(binary.len(), 16);
+        let ids: Vec<i64> = binary
+            .chunks_exact(8)
+            .map(|bytes| i64::from_be_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert_eq!(ids, [pack(1_000, 3, 1), pack(1_000, 4, 1)]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_write_ids_returns_generation_errors_as_io_errors() {
+        let mut snowflake = near_ceiling_snowflake(OverflowPolicy::default());
+        let mut out = Vec::new();
+        let error = snowflake
+            .write_ids(&mut out, 1, IdFormat::Decimal)
+            .unwrap_err();
+        assert!(out.is_empty());
+        let error = error.into_inner().unwrap();
+        assert!(matches!(
+            error.downcast_ref::<SnowflakeError>(),
+            Some(SnowflakeError::TimestampOverflowError { .. })
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_service_id_offset_maps_service_ids() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(4)
+            .service_id_offset(1)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let id = snowflake.gen().unwrap();
+        assert_eq!(decode(id), (1_000, 0, 3));
+        assert_eq!(snowflake.service_id(), 4);
+        assert_eq!(snowflake.config().service_id, 3);
+        assert_eq!(snowflake.decode(id).unwrap().service_id, 4);
+
+        for (service_id, error) in [
+            (
+                0,
+                SnowflakeError::ServiceIdBelowOffsetError {
+                    service_id: 0,
+                    offset: 1,
+                },
+            ),
+            (
+                5,
+                SnowflakeError::InvalidServiceIdError {
+                    service_id: 5,
+                    max: 4,
+                },
+            ),
+        ] {
+            let result = Snowflake::builder(service_id).service_id_offset(1).build();
+            assert_eq!(result.unwrap_err().to_string(), error.to_string());
+        }
+
+        let resumed = Snowflake::builder(1)
+            .service_id_offset(1)
+            .clock(clock)
+            .resume_from(pack(1_000, 0, 2))
+            .build();
+        assert!(matches!(
+            resumed,
+            Err(SnowflakeError::ServiceIdMismatchError {
+                expected: 1,
+                found: 3
+            })
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_validate_for_reports_each_unmet_constraint() {
+        // 1024 ids per millisecond, 4 services and a lifespan of 1024 seconds
+        let snowflake = Snowflake::builder(0)
+            .layout(SnowflakeLayout::new(20, 10, 2).unwrap())
+            .allow_short_lifespan()
+            .epoch(UNIX_EPOCH + Duration::from_secs(1_000))
+            .build()
+            .unwrap();
+        let lifespan_end = UNIX_EPOCH + Duration::from_millis(1_000_000 + (1 << 20));
+        let before_end = lifespan_end - Duration::from_millis(1);
+        assert_eq!(snowflake.validate_for(1_024_000, 4, before_end), Ok(()));
+
+        assert_eq!(
+            snowflake.validate_for(1_024_001, 4, before_end),
+            Err(LayoutProblem::ThroughputTooHighError {
+                expected_qps: 1_024_001,
+                max_qps: 1_024_000
+            })
+        );
+        assert_eq!(
+            snowflake.validate_for(1_000, 5, before_end),
+            Err(LayoutProblem::TooManyServicesError {
+                service_count: 5,
+                max_services: 4
+            })
+        );
+        assert_eq!(
+            snowflake.validate_for(1_000, 1, lifespan_end),
+            Err(LayoutProblem::TimestampOverflowError {
+                until: lifespan_end,
+                overflows_at: lifespan_end
+            })
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_capacity_between_multiplies_millis_sequences_and_services() {
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        let snowflake = Snowflake::new(0).unwrap();
+        // 131072 ids per millisecond for each of 4 services
+        assert_eq!(
+            snowflake.capacity_between(start, start + Duration::from_secs(1)),
+            1_000 * 131_072 * 4
+        );
+        assert_eq!(
+            snowflake.capacity_between(start, start + Duration::from_micros(2_999)),
+            2 * 131_072 * 4
+        );
+        assert_eq!(snowflake.capacity_between(start, start), 0);
+        assert_eq!(
+            snowflake.capacity_between(start + Duration::from_secs(1), start),
+            0
+        );
+
+        // 4096 ids per millisecond for each of 1024 services
+        let discord = Snowflake::discord_builder(0).build().unwrap();
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert_eq!(
+            discord.capacity_between(start, start + day),
+            86_400_000 * 4_096 * 1_024
+        );
+        assert_eq!(
+            discord.capacity_between(start, start + 1_000 * 365 * day),
+            u64::MAX
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_assign_service_ids_hands_out_distinct_ids() {
+        let snowflake = Snowflake::builder(3)
+            .layout(SnowflakeLayout::new(41, 12, 4).unwrap())
+            .service_id_offset(3)
+            .build()
+            .unwrap();
+        let service_ids = snowflake.assign_service_ids(16).unwrap();
+        assert_eq!(service_ids, (3..19).collect::<Vec<u16>>());
+        for service_id in &service_ids {
+            assert!(snowflake.sibling(*service_id).is_ok());
+        }
+        assert_eq!(snowflake.assign_service_ids(2).unwrap(), [3, 4]);
+        assert!(snowflake.assign_service_ids(0).unwrap().is_empty());
+
+        let e = snowflake.assign_service_ids(17).unwrap_err();
+        assert_eq!(
+            e,
+            LayoutProblem::TooManyServicesError {
+                service_count: 17,
+                max_services: 16
+            }
+        );
+        assert_eq!(
+            e.to_string(),
+            "17 services need more than the 16 available service ids, a layout with 5 service id bits would fit them"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_throughput_probe_runs_for_the_duration() {
+        let mut snowflake = Snowflake::new(0).unwrap();
+        let start = Instant::now();
+        let count = snowflake
+            .throughput_probe(Duration::from_millis(20))
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert!(count > 0);
+        assert_eq!(count % 1_024, 0);
+        assert!(count <= (MAX_17_BITS as u64 + 1) * (start.elapsed().as_millis() as u64 + 1));
+    }
+
+    #[test]
+    fn test_generated_ids_fall_between_min_and_max_id() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let layout = SnowflakeLayout::new(12, 4, 3).unwrap();
+        let mut snowflake = Snowflake::builder(7)
+            .layout(layout)
+            .allow_short_lifespan()
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert_eq!((snowflake.min_id(), snowflake.max_id()), (0, (1 << 19) - 1));
+        for _ in 0..1_000 {
+            let id = snowflake.gen().unwrap();
+            assert!((snowflake.min_id()..=snowflake.max_id()).contains(&id));
+        }
+        // the last millisecond of the layout
+        clock.set(Duration::from_millis(4_095));
+        for _ in 0..16 {
+            let id = snowflake.gen().unwrap();
+            assert!((snowflake.min_id()..=snowflake.max_id()).contains(&id));
+        }
+        assert_eq!(snowflake.max_id(), layout.pack(4_095, 15, 7),);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_max_concurrent_services() {
+        assert_eq!(Snowflake::new(0).unwrap().max_concurrent_services(), 4);
+        for (service_id_bits, max) in [(0, 1), (10, 1_024), (16, 65_536)] {
+            let snowflake = Snowflake::builder(0)
+                .layout(SnowflakeLayout::new(41, 0, service_id_bits).unwrap())
+                .build()
+                .unwrap();
+            assert_eq!(snowflake.max_concurrent_services(), max);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_display_summarizes_config() {
+        let snowflake =
+            Snowflake::with_epoch(2, UNIX_EPOCH + Duration::from_secs(1_577_836_800)).unwrap();
+        assert_eq!(
+            snowflake.to_string(),
+            "Snowflake(service_id=2, epoch=2020-01-01T00:00:00Z, layout=44/17/2)"
+        );
+    }
+
+    #[test]
+    fn test_rfc3339_formats_dates() {
+        for (millis, date) in [
+            (0, "1970-01-01T00:00:00Z"),
+            (951_782_400_000, "2000-02-29T00:00:00Z"),
+            (1_420_070_400_000, "2015-01-01T00:00:00Z"),
+            (1_462_015_105_796, "2016-04-30T11:18:25.796Z"),
+            (4_102_444_799_999, "2099-12-31T23:59:59.999Z"),
+        ] {
+            assert_eq!(Rfc3339(millis).to_string(), date);
+        }
+        assert_eq!(Rfc3339(-86_400_000).to_string(), "1969-12-31T00:00:00Z");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_config_compares_generators() {
+        let first = Snowflake::new(1).unwrap();
+        let second = Snowflake::builder(1)
+            .clock(MockClock::default())
+            .build()
+            .unwrap();
+        assert_eq!(first.config(), second.config());
+        assert_eq!(
+            first.config(),
+            SnowflakeConfig {
+                epoch: UNIX_EPOCH,
+                service_id: 1,
+                layout: SnowflakeLayout::DEFAULT,
+            }
+        );
+
+        let other_epoch = Snowflake::with_epoch(1, UNIX_EPOCH + Duration::from_secs(1)).unwrap();
+        let other_service_id = Snowflake::new(2).unwrap();
+        let other_layout = Snowflake::builder(1)
+            .layout(SnowflakeLayout::DISCORD)
+            .build()
+            .unwrap();
+        for other in [other_epoch, other_service_id, other_layout] {
+            assert_ne!(first.config(), other.config());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_u64_source_generates_ids() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(2).clock(clock).build().unwrap();
+        let mut source = snowflake.as_u64_source();
+        let ids: Vec<u64> = (0..3).map(|_| source()).collect();
+        assert_eq!(
+            ids,
+            [pack(1_000, 0, 2), pack(1_000, 1, 2), pack(1_000, 2, 2)].map(|id| id as u64)
+        );
+        drop(source);
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_000, 3, 2));
+        assert_eq!(snowflake.gen_u64().unwrap(), pack(1_000, 4, 2) as u64);
+
+        let mut concurrent = Snowflake::builder(2)
+            .clock(MockClock::new(Duration::from_millis(2_000)))
+            .build_concurrent()
+            .unwrap();
+        assert_eq!(concurrent.gen_u64().unwrap(), pack(2_000, 0, 2) as u64);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_is_plausible_rejects_impossible_ids() {
+        let clock = MockClock::new(Duration::from_millis(10_000));
+        let mut snowflake = Snowflake::builder(0)
+            .epoch(UNIX_EPOCH + Duration::from_millis(4_000))
+            .clock(clock)
+            .build()
+            .unwrap();
+        let id = snowflake.gen().unwrap();
+        assert!(snowflake.is_plausible(id));
+        assert!(snowflake.is_plausible(pack(0, 0, 0)));
+        assert!(snowflake.is_plausible(pack(7_000, 5, 3)));
+        assert!(!snowflake.is_plausible(pack(7_001, 0, 0)));
+        assert!(!snowflake.is_plausible(-pack(6_000, 0, 0)));
+
+        let small = Snowflake::builder(0)
+            .layout(SnowflakeLayout::new(20, 2, 2).unwrap())
+            .allow_short_lifespan()
+            .build()
+            .unwrap();
+        assert!(!small.is_plausible(1 << 24));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_sequence_fraction_normalizes_against_the_layout() {
+        let snowflake = Snowflake::new(1).unwrap();
+        assert_eq!(snowflake.sequence_fraction(pack(1_000, 0, 1)), 0.0);
+        assert_eq!(
+            snowflake.sequence_fraction(pack(1_000, MAX_17_BITS, 1)),
+            1.0
+        );
+
+        let layout = SnowflakeLayout::new(44, 2, 2).unwrap();
+        let snowflake = Snowflake::builder(1).layout(layout).build().unwrap();
+        let fractions: Vec<f64> = (0..4)
+            .map(|seq| snowflake.sequence_fraction(layout.pack(1_000, seq, 1)))
+            .collect();
+        assert_eq!(fractions, [0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0]);
+
+        let layout = SnowflakeLayout::new(44, 0, 2).unwrap();
+        let snowflake = Snowflake::builder(1).layout(layout).build().unwrap();
+        assert_eq!(snowflake.sequence_fraction(layout.pack(1_000, 0, 1)), 0.0);
+    }
+
+    #[test]
+    fn test_gen_future_dates_ids_without_touching_the_sequence() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1).clock(clock).build().unwrap();
+        let future = snowflake.gen_future(Duration::from_secs(60)).unwrap();
+        assert_eq!(decode(future), (61_000, 0, 1));
+        assert!(!snowflake.is_plausible(future));
+        assert_eq!(snowflake.gen().unwrap(), pack(1_000, 0, 1));
+
+        assert!(matches!(
+            snowflake.gen_future(Duration::from_millis(MAX_44_BITS as u64)),
+            Err(SnowflakeError::TimestampOverflowError {
+                millis,
+                max: MAX_44_BITS
+            }) if millis == MAX_44_BITS as u128 + 1_000
+        ));
+        assert!(snowflake.gen_future(Duration::MAX).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_shard_prefix_groups_ids_by_time() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1).clock(clock.clone()).build().unwrap();
+        let first = snowflake.gen().unwrap();
+        let second = snowflake.gen().unwrap();
+        clock.advance(Duration::from_millis(1));
+        let next = snowflake.gen().unwrap();
+
+        // the 44 timestamp bits are the first of the 63 bits an id uses
+        for id in [first, second] {
+            assert_eq!(snowflake.shard_prefix(id, 44), 1_000);
+        }
+        assert_eq!(snowflake.shard_prefix(next, 44), 1_001);
+        assert_eq!(snowflake.shard_prefix(first, 40), 1_000 >> 4);
+        assert_eq!(snowflake.shard_prefix(next, 40), 1_001 >> 4);
+        assert_eq!(snowflake.shard_prefix(first, 0), 0);
+        assert_eq!(snowflake.shard_prefix(first, 64), first as u64);
+
+        // layouts using fewer bits count from their own top bit
+        let layout = SnowflakeLayout::new(36, 10, 2).unwrap();
+        let small = Snowflake::builder(1).layout(layout).build().unwrap();
+        assert_eq!(small.shard_prefix(layout.pack(1_000, 5, 1), 36), 1_000);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_current_timestamp_field_does_not_consume_sequence() {
+        let clock = MockClock::new(Duration::from_micros(1_000_400));
+        let mut snowflake = Snowflake::builder(0).clock(clock.clone()).build().unwrap();
+        assert_eq!(snowflake.current_timestamp_field().unwrap(), 1_000);
+        assert_eq!(snowflake.current_timestamp_field().unwrap(), 1_000);
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_000, 0, 0));
+
+        clock.set(Duration::from_millis(MAX_44_BITS as u64 + 1));
+        assert!(matches!(
+            snowflake.current_timestamp_field(),
+            Err(SnowflakeError::TimestampOverflowError {
+                max: MAX_44_BITS,
+                ..
+            })
+        ));
+
+        let snowflake = Snowflake::builder(0)
+            .clock(clock)
+            .epoch(UNIX_EPOCH + Duration::from_millis(MAX_44_BITS as u64 + 3))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            snowflake.current_timestamp_field(),
+            Err(SnowflakeError::ClockBeforeEpochError { behind }) if behind == Duration::from_millis(2)
+        ));
+    }
+
+    #[test]
+    fn test_small_backward_skew_is_waited_out() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(0)
+            .clock(clock.clone())
+            .max_backward_skew(Duration::from_millis(5))
+            .build()
+            .unwrap();
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_000, 0, 0));
+        clock.set(Duration::from_micros(996_300));
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_000, 1, 0));
+        assert_eq!(clock.now(), Duration::from_millis(1_000));
+    }
+
+    #[test]
+    fn test_large_backward_skew_returns_clock_went_backward_error() {
+        let clock = MockClock::new(Duration::from_millis(10_000));
+        let mut snowflake = Snowflake::builder(0)
+            .clock(clock.clone())
+            .max_backward_skew(Duration::from_millis(5))
+            .build()
+            .unwrap();
+        assert_eq!(decode(snowflake.gen().unwrap()), (10_000, 0, 0));
+        clock.set(Duration::from_millis(7_000));
+        assert!(matches!(
+            snowflake.gen(),
+            Err(SnowflakeError::ClockWentBackwardError { behind }) if behind == Duration::from_secs(3)
+        ));
+        assert_eq!(clock.now(), Duration::from_millis(7_000));
+    }
+
+    #[test]
+    fn test_zero_backward_skew_errors_right_away() {
+        let clock = MockClock::new(Duration::from_millis(10_000));
+        let mut snowflake = Snowflake::builder(0)
+            .clock(clock.clone())
+            .max_backward_skew(Duration::ZERO)
+            .build()
+            .unwrap();
+        snowflake.gen().unwrap();
+        clock.set(Duration::from_millis(9_999));
+        assert!(matches!(
+            snowflake.gen(),
+            Err(SnowflakeError::ClockWentBackwardError { behind }) if behind == Duration::from_millis(1)
+        ));
+        // the last id isn't lost, once the clock is back the sequence carries on
+        clock.set(Duration::from_millis(10_000));
+        assert_eq!(decode(snowflake.gen().unwrap()), (10_000, 1, 0));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_clock_stepped_before_the_epoch_is_an_error() {
+        let clock = MockClock::new(Duration::from_millis(10_000));
+        let epoch = UNIX_EPOCH + Duration::from_millis(5_000);
+        let mut snowflake = Snowflake::builder(0)
+            .clock(clock.clone())
+            .epoch(epoch)
+            .build()
+            .unwrap();
+        assert_eq!(decode(snowflake.gen().unwrap()), (5_000, 0, 0));
+        clock.set(Duration::from_millis(4_000));
+        assert!(matches!(
+            snowflake.gen(),
+            Err(SnowflakeError::ClockBeforeEpochError { behind }) if behind == Duration::from_secs(1)
+        ));
+        assert_eq!(
+            snowflake.remaining_timestamp_range(),
+            Duration::from_millis(MAX_44_BITS as u64)
+        );
+        clock.set(Duration::from_millis(10_000));
+        assert_eq!(decode(snowflake.gen().unwrap()), (5_000, 1, 0));
+    }
+
+    fn near_ceiling_snowflake(overflow_policy: OverflowPolicy) -> Snowflake<MockClock> {
+        // a 1023ms lifespan with 4 ids per millisecond
+        Snowflake::builder(0)
+            .layout(SnowflakeLayout::new(10, 2, 2).unwrap())
+            .allow_short_lifespan()
+            .clock(MockClock::new(Duration::from_millis(1_030)))
+            .overflow_policy(overflow_policy)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_timestamp_overflow_errors_by_default() {
+        let mut snowflake = near_ceiling_snowflake(OverflowPolicy::default());
+        assert!(matches!(
+            snowflake.gen(),
+            Err(SnowflakeError::TimestampOverflowError {
+                millis: 1_030,
+                max: 1_023
+            })
+        ));
+    }
+
+    #[test]
+    fn test_timestamp_overflow_saturates_until_sequence_is_exhausted() {
+        let mut snowflake = near_ceiling_snowflake(OverflowPolicy::Saturate);
+        let layout = snowflake.layout();
+        for seq in 0..4 {
+            assert_eq!(layout.decode(snowflake.gen().unwrap()), (1_023, seq, 0));
+        }
+        assert!(matches!(
+            snowflake.gen(),
+            Err(SnowflakeError::TimestampOverflowError { .. })
+        ));
+        snowflake.clock.advance(Duration::from_millis(5));
+        assert!(matches!(
+            snowflake.gen(),
+            Err(SnowflakeError::TimestampOverflowError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_timestamp_overflow_wraps() {
+        let mut snowflake = near_ceiling_snowflake(OverflowPolicy::Wrap);
+        let layout = snowflake.layout();
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (6, 0, 0));
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (6, 1, 0));
+        snowflake.clock.advance(Duration::from_millis(1));
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (7, 0, 0));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_second_boundary_ids_bucket_ids_by_second() {
+        let clock = MockClock::new(Duration::from_millis(4_990));
+        let mut snowflake = Snowflake::builder(3)
+            .epoch(UNIX_EPOCH + Duration::from_millis(250))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let start = snowflake.second_boundary_id(UNIX_EPOCH + Duration::from_secs(5));
+        let end = snowflake.second_boundary_id(UNIX_EPOCH + Duration::from_micros(5_000_001));
+        assert_eq!(start, pack(4_750, 0, 0));
+        assert_eq!(end, pack(5_750, 0, 0));
+        assert_eq!(
+            snowflake.second_boundary_id(UNIX_EPOCH + Duration::from_micros(4_000_001)),
+            start
+        );
+
+        for millis in 4_990..6_010 {
+            clock.set(Duration::from_millis(millis));
+            let id = snowflake.gen().unwrap();
+            let in_fifth_second = (5_000..6_000).contains(&millis);
+            assert_eq!(start <= id && id < end, in_fifth_second, "{millis}");
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_wait_strategies_reach_the_next_millisecond() {
+        for wait_strategy in [
+            WaitStrategy::Sleep,
+            WaitStrategy::Spin,
+            WaitStrategy::SpinThenSleep {
+                spin_for: Duration::from_micros(50),
+            },
+        ] {
+            let mut snowflake = Snowflake::builder(0)
+                .layout(SnowflakeLayout::new(44, 2, 2).unwrap())
+                .wait_strategy(wait_strategy)
+                .build()
+                .unwrap();
+            let layout = snowflake.layout();
+            let ids: Vec<i64> = (0..20).map(|_| snowflake.gen().unwrap()).collect();
+            assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+            let first = layout.timestamp_bits_of(ids[0]);
+            let last = layout.timestamp_bits_of(ids[19]);
+            assert!(last - first >= 4, "{wait_strategy:?}");
+        }
+    }
+
+    // spinning is timed on an Instant, the frozen clock can't time it
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_spinning_on_a_stalled_clock_returns_clock_stalled_error() {
+        for wait_strategy in [
+            WaitStrategy::Spin,
+            WaitStrategy::SpinThenSleep {
+                spin_for: Duration::from_millis(1),
+            },
+        ] {
+            let mut snowflake = Snowflake::builder(0)
+                .layout(SnowflakeLayout::new(44, 2, 2).unwrap())
+                .clock(MockClock::frozen(Duration::from_millis(1_000)))
+                .wait_strategy(wait_strategy)
+                .max_clock_stall(Duration::from_millis(2))
+                .build()
+                .unwrap();
+            for _ in 0..4 {
+                snowflake.gen().unwrap();
+            }
+            assert!(matches!(
+                snowflake.gen(),
+                Err(SnowflakeError::ClockStalledError { waited }) if waited >= Duration::from_millis(2)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_stalled_clock_returns_clock_stalled_error() {
+        let clock = MockClock::frozen(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(0)
+            .clock(clock.clone())
+            .max_clock_stall(Duration::from_millis(5))
+            .build()
+            .unwrap();
+        for _ in 0..=MAX_17_BITS {
+            snowflake.gen().unwrap();
+        }
+        assert!(matches!(
+            snowflake.gen(),
+            Err(SnowflakeError::ClockStalledError { .. })
+        ));
+
+        // once the clock moves again, so does the generator
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_001, 0, 0));
+    }
+
+    #[test]
+    fn test_ids_carry_the_time_of_the_clock() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1).clock(clock.clone()).build().unwrap();
+        assert_eq!(timestamp_bits_of(snowflake.gen().unwrap()), 1_000);
+        clock.advance(Duration::from_millis(5));
+        assert_eq!(timestamp_bits_of(snowflake.gen().unwrap()), 1_005);
+    }
+
+    // a MockClock that records how long it was asked to sleep
+    #[derive(Clone)]
+    struct SleepRecordingClock {
+        clock: MockClock,
+        sleeps: Arc<Mutex<Vec<Duration>>>,
+    }
+
+    impl Clock for SleepRecordingClock {
+        fn now(&self) -> Duration {
+            self.clock.now()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.sleeps.lock().unwrap().push(duration);
+            self.clock.sleep(duration);
+        }
+    }
+
+    #[test]
+    fn test_exhausted_sequence_sleeps_at_most_until_the_next_millisecond() {
+        // late in a second as well as early in one, so waits measured against the
+        // second instead of the millisecond would be way off
+        for now in [
+            Duration::from_micros(7_999_900),
+            Duration::from_micros(8_000_001),
+            Duration::from_micros(8_123_456),
+        ] {
+            let clock = SleepRecordingClock {
+                clock: MockClock::new(now),
+                sleeps: Arc::new(Mutex::new(Vec::new())),
+            };
+            let mut snowflake = Snowflake::builder(0).clock(clock.clone()).build().unwrap();
+            let first = snowflake.gen().unwrap();
+            for _ in 0..MAX_17_BITS {
+                snowflake.gen().unwrap();
+            }
+            let next = snowflake.gen().unwrap();
+            assert_eq!(timestamp_bits_of(next), timestamp_bits_of(first) + 1);
+            assert_eq!(sequence_of(next), 0);
+
+            let sleeps = clock.sleeps.lock().unwrap();
+            let remaining = Duration::from_millis(now.as_millis() as u64 + 1) - now;
+            assert_eq!(*sleeps, [remaining]);
+            assert!(sleeps[0] <= Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_resume_from_continues_after_last_id() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let last_id = pack(1_000, 41, 2);
+        let mut snowflake = Snowflake::builder(2)
+            .clock(clock.clone())
+            .resume_from(last_id)
+            .build()
+            .unwrap();
+        assert_eq!(snowflake.gen().unwrap(), pack(1_000, 42, 2));
+
+        // the clock being behind the last id doesn't make ids go backwards
+        let last_id = pack(5_000, 7, 2);
+        let mut snowflake = Snowflake::builder(2)
+            .clock(clock.clone())
+            .resume_from(last_id)
+            .build()
+            .unwrap();
+        assert_eq!(snowflake.gen().unwrap(), pack(5_000, 8, 2));
+
+        // an exhausted sequence moves on to the next millisecond
+        let last_id = pack(1_000, MAX_17_BITS, 2);
+        let mut snowflake = Snowflake::builder(2)
+            .clock(clock)
+            .resume_from(last_id)
+            .build()
+            .unwrap();
+        assert_eq!(snowflake.gen().unwrap(), pack(1_001, 0, 2));
+    }
+
+    #[test]
+    fn test_resume_from_with_random_sequence_start_moves_on_to_the_next_millisecond() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        // e.g. the last of the ids from a start at 100 that wrapped around to 5
+        let last_id = pack(1_000, 5, 2);
+        let mut snowflake = Snowflake::builder(2)
+            .clock(clock.clone())
+            .random_sequence_start(true)
+            .resume_from(last_id)
+            .build()
+            .unwrap();
+        let id = snowflake.gen().unwrap();
+        assert_eq!(timestamp_bits_of(id), 1_001);
+        assert_eq!(clock.now(), Duration::from_millis(1_001));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_resume_from_creates_ids_greater_than_last_id() {
+        let last_id = Snowflake::new(1).unwrap().gen().unwrap();
+        let mut snowflake = Snowflake::resume_from(1, UNIX_EPOCH, last_id).unwrap();
+        assert!((0..10_000).all(|_| snowflake.gen().unwrap() > last_id));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_resume_from_rejects_id_of_other_service() {
+        assert!(matches!(
+            Snowflake::resume_from(1, UNIX_EPOCH, pack(1_000, 0, 3)),
+            Err(SnowflakeError::ServiceIdMismatchError {
+                expected: 1,
+                found: 3
+            })
+        ));
+        assert!(matches!(
+            Snowflake::resume_from(1, UNIX_EPOCH, -1),
+            Err(SnowflakeError::NegativeIdError { id: -1 })
+        ));
+    }
+
+    #[test]
+    fn test_gen_id_is_the_next_id() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1).clock(clock).build().unwrap();
+        let id = snowflake.gen_id().unwrap();
+        assert_eq!(id.get(), pack(1_000, 0, 1));
+        assert_eq!(i64::from(snowflake.gen_id().unwrap()), pack(1_000, 1, 1));
+    }
+
+    #[test]
+    fn test_gen_with_ordinal_increases_by_one_per_call() {
+        let clock = MockClock::new(Duration::from_millis(5_000));
+        let mut snowflake = Snowflake::builder(0).clock(clock.clone()).build().unwrap();
+        assert_eq!(snowflake.gen_with_ordinal().unwrap().1, 1);
+        snowflake.gen().unwrap();
+        assert_eq!(snowflake.gen_with_ordinal().unwrap().1, 3);
+
+        // the ordinal keeps counting even when the clock goes backwards
+        clock.set(Duration::from_millis(1_000));
+        let mut last = 3;
+        for _ in 0..1_000 {
+            let (_, ordinal) = snowflake.gen_with_ordinal().unwrap();
+            assert_eq!(ordinal, last + 1);
+            last = ordinal;
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_id_range_for_contains_ids_generated_in_window() {
+        let mut snowflake = Snowflake::new(1).unwrap();
+        let start = SystemTime::now();
+        let ids: Vec<i64> = (0..10_000).map(|_| snowflake.gen().unwrap()).collect();
+        let end = SystemTime::now();
+
+        let (min, max) = snowflake.id_range_for(start, end);
+        assert!(ids.iter().all(|id| (min..=max).contains(id)));
+
+        let before = snowflake.id_range_for(
+            start - Duration::from_secs(2),
+            start - Duration::from_secs(1),
+        );
+        assert!(ids.iter().all(|id| *id > before.1));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_id_range_for_clamps_to_timestamp_range() {
+        let epoch = UNIX_EPOCH + Duration::from_secs(60);
+        let snowflake = Snowflake::with_epoch(0, epoch).unwrap();
+        let far_future = epoch + Duration::from_millis(MAX_44_BITS as u64) * 2;
+        assert_eq!(
+            snowflake.id_range_for(UNIX_EPOCH, far_future),
+            (0, i64::MAX)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_invalid_service_id_error_reports_value_and_max() {
+        let e = Snowflake::new(7).unwrap_err();
+        assert!(matches!(
+            e,
+            SnowflakeError::InvalidServiceIdError {
+                service_id: 7,
+                max: 3
+            }
+        ));
+        assert_eq!(e.to_string(), "service id 7 exceeds maximum 3");
+    }
+
+    #[test]
+    fn test_custom_layout_packs_fields_in_its_widths() {
+        let layout = SnowflakeLayout::new(41, 12, 10).unwrap();
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1_000)
+            .layout(layout)
+            .clock(clock)
+            .build()
+            .unwrap();
+        let id = snowflake.gen().unwrap();
+        assert_eq!(id, 1_000 << 22 | 1_000);
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (1_000, 1, 1_000));
+
+        // the sequence is exhausted after 4096 ids per millisecond
+        for _ in 2..4_096 {
+            snowflake.gen().unwrap();
+        }
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (1_001, 0, 1_000));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_infer_epoch_agrees_on_the_epoch_of_samples() {
+        let epoch = UNIX_EPOCH + Duration::from_secs(1_420_070_400);
+        let layout = SnowflakeLayout::DISCORD;
+        let sample = |millis: i64, off_by: Duration| {
+            let created = epoch + Duration::from_millis(millis as u64) + off_by;
+            (layout.pack(millis, 3, 7), created)
+        };
+        let samples = [
+            sample(1_000_000, Duration::from_secs(2)),
+            sample(5_000_000, Duration::ZERO),
+            sample(9_000_000, Duration::from_secs(30)),
+        ];
+        assert_eq!(
+            Snowflake::infer_epoch(&samples, &layout),
+            Some(epoch + Duration::from_secs(2))
+        );
+
+        // the default layout reads other timestamp bits, so its epochs disagree
+        assert_eq!(
+            Snowflake::infer_epoch(&samples, &SnowflakeLayout::DEFAULT),
+            None
+        );
+        let disagreeing = [samples[0], sample(2_000_000, Duration::from_secs(90))];
+        assert_eq!(Snowflake::infer_epoch(&disagreeing, &layout), None);
+        assert_eq!(Snowflake::infer_epoch(&[], &layout), None);
+        assert_eq!(
+            Snowflake::infer_epoch(&[(-1, SystemTime::now())], &layout),
+            None
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_sibling_shares_epoch_and_layout() {
+        let clock = MockClock::new(Duration::from_millis(5_000));
+        let layout = SnowflakeLayout::DISCORD;
+        let mut snowflake = Snowflake::builder(10)
+            .layout(layout)
+            .epoch(UNIX_EPOCH + Duration::from_secs(1))
+            .service_id_offset(10)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let mut sibling = snowflake.sibling(20).unwrap();
+        assert_eq!(sibling.epoch(), snowflake.epoch());
+        assert_eq!(sibling.layout(), layout);
+        assert_eq!(sibling.service_id(), 20);
+        assert_eq!(layout.decode(snowflake.gen().unwrap()), (4_000, 0, 0));
+        assert_eq!(layout.decode(sibling.gen().unwrap()), (4_000, 0, 10));
+
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(layout.decode(sibling.gen().unwrap()), (4_001, 0, 10));
+
+        assert!(matches!(
+            snowflake.sibling(5),
+            Err(SnowflakeError::ServiceIdBelowOffsetError {
+                service_id: 5,
+                offset: 10
+            })
+        ));
+        assert!(matches!(
+            snowflake.sibling(1_034),
+            Err(SnowflakeError::InvalidServiceIdError { .. })
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_short_lifespan_layouts_need_an_opt_in() {
+        let layout = SnowflakeLayout::new(20, 10, 2).unwrap();
+        let e = Snowflake::builder(0).layout(layout).build().unwrap_err();
+        assert!(matches!(
+            e,
+            SnowflakeError::ShortLifespanError {
+                timestamp_bits: 20,
+                lifespan
+            } if lifespan == Duration::from_millis((1 << 20) - 1)
+        ));
+        assert_eq!(
+            e.to_string(),
+            "20 timestamp bits overflow 1048.575s after the epoch, which is less than 31536000s"
+        );
+        assert!(Snowflake::builder(0)
+            .layout(layout)
+            .allow_short_lifespan()
+            .build()
+            .is_ok());
+
+        // 35 bits are the fewest that last a year
+        let layout = SnowflakeLayout::new(34, 17, 2).unwrap();
+        assert!(Snowflake::builder(0).layout(layout).build().is_err());
+        let layout = SnowflakeLayout::new(35, 17, 2).unwrap();
+        assert!(Snowflake::builder(0).layout(layout).build().is_ok());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_custom_layout_validates_service_id_against_its_width() {
+        let layout = SnowflakeLayout::new(41, 12, 10).unwrap();
+        assert!(Snowflake::builder(1_023).layout(layout).build().is_ok());
+        assert!(matches!(
+            Snowflake::builder(1_024).layout(layout).build(),
+            Err(SnowflakeError::InvalidServiceIdError {
+                service_id: 1_024,
+                max: 1_023
+            })
+        ));
+        let mut snowflake = Snowflake::with_layout(1_000, layout).unwrap();
+        assert_eq!(layout.service_id_of(snowflake.gen().unwrap()), 1_000);
+        assert!(Snowflake::with_layout(1_024, layout).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_custom_layout_remaining_timestamp_range() {
+        const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+        // 33 bits of milliseconds are just under 100 days
+        let layout = SnowflakeLayout::new(33, 17, 2).unwrap();
+        let snowflake = Snowflake::builder(0)
+            .layout(layout)
+            .allow_short_lifespan()
+            .epoch(SystemTime::now())
+            .build()
+            .unwrap();
+        let remaining = snowflake.remaining_timestamp_range();
+        assert!(remaining > 99 * DAY && remaining < 100 * DAY);
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_epoch_from_rfc3339_date() {
+        let snowflake = Snowflake::with_epoch_rfc3339(1, "2020-01-01T00:00:00Z").unwrap();
+        assert_eq!(
+            snowflake.epoch(),
+            UNIX_EPOCH + Duration::from_secs(1_577_836_800)
+        );
+        let snowflake = Snowflake::with_epoch_rfc3339(1, "2020-01-01T02:00:00.5+02:00").unwrap();
+        assert_eq!(
+            snowflake.epoch(),
+            UNIX_EPOCH + Duration::from_millis(1_577_836_800_500)
+        );
+
+        assert!(matches!(
+            Snowflake::with_epoch_rfc3339(1, "2020-01-01"),
+            Err(SnowflakeError::InvalidEpochStringError { .. })
+        ));
+        assert!(matches!(
+            Snowflake::with_epoch_rfc3339(1, "2999-01-01T00:00:00Z"),
+            Err(SnowflakeError::EpochInFutureError { .. })
+        ));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_nonce_ids_stay_unique_under_saturation() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let layout = SnowflakeLayout::DEFAULT.with_nonce_bits(8).unwrap();
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1)
+            .layout(layout)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        // 512 ids per millisecond, so these fill 10 milliseconds
+        let mut ids: Vec<i64> = (0..5_120)
+            .map(|_| snowflake.gen_with_nonce(&mut rng).unwrap())
+            .collect();
+        assert_eq!(clock.now(), Duration::from_millis(1_009));
+        assert!(ids.iter().any(|id| layout.nonce_of(*id) != 0));
+        assert!(ids.iter().all(|id| layout.service_id_of(*id) == 1));
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 5_120);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_random_service_id_is_within_range() {
+        for layout in [
+            SnowflakeLayout::DEFAULT,
+            SnowflakeLayout::new(41, 12, 10).unwrap(),
+            SnowflakeLayout::new(40, 7, 16).unwrap(),
+            SnowflakeLayout::new(63, 0, 0).unwrap(),
+        ] {
+            for seed in 0..100 {
+                let snowflake = Snowflake::with_random_service_id(layout, Some(seed)).unwrap();
+                assert!(snowflake.service_id <= layout.max_service_id());
+            }
+            let snowflake = Snowflake::with_random_service_id(layout, None).unwrap();
+            assert!(snowflake.service_id <= layout.max_service_id());
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_random_service_id_is_reproducible_from_seed() {
+        let layout = SnowflakeLayout::new(40, 7, 16).unwrap();
+        let first = Snowflake::with_random_service_id(layout, Some(42)).unwrap();
+        let second = Snowflake::with_random_service_id(layout, Some(42)).unwrap();
+        assert_eq!(first.service_id, second.service_id);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_snowflake_creates_unique_positive_ids() {
+        let mut snowflake = Snowflake::new(0).unwrap();
+        let mut ids: Vec<i64> = Vec::new();
+        for _ in 0..NUM_IDS {
+            ids.push(snowflake.gen().unwrap());
+        }
+        ids.sort();
+        ids.dedup();
+        ids = ids.into_iter().filter(|id| *id > 0).collect();
+        assert_eq!(ids.len(), NUM_IDS as usize);
+    }
+
+    #[cfg(feature = "std")]
+    fn poison(snowflake: &ConcurrentSnowflake) {
+        let inner = Arc::clone(&snowflake.inner);
+        let _ = std::thread::spawn(move || {
+            let _guard = inner.lock().unwrap();
+            panic!("poisoning the lock");
+        })
+        .join();
+        assert!(snowflake.inner.is_poisoned());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_propagate_poison_policy_returns_poison_error() {
+        let mut snowflake = ConcurrentSnowflake::new(0).unwrap();
+        poison(&snowflake);
+        assert!(matches!(
+            snowflake.gen(),
+            Err(ConcurrentSnowflakeError::PoisonError)
+        ));
+        assert!(matches!(
+            snowflake.gen(),
+            Err(ConcurrentSnowflakeError::PoisonError)
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_recover_poison_policy_keeps_generating() {
+        let mut snowflake = Snowflake::builder(0)
+            .poison_policy(PoisonPolicy::Recover)
+            .build_concurrent()
+            .unwrap();
+        let first = snowflake.gen().unwrap();
+        poison(&snowflake);
+        let second = snowflake.gen().unwrap();
+        assert!(!snowflake.inner.is_poisoned());
+        assert!(second > first);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_paused_generator_errors_until_resumed() {
+        let mut snowflake = ConcurrentSnowflake::new(0).unwrap();
+        let mut clone = snowflake.clone();
+        let before = snowflake.gen().unwrap();
+
+        clone.pause();
+        assert!(snowflake.is_paused());
+        for generator in [&mut snowflake, &mut clone] {
+            assert!(matches!(
+                generator.gen(),
+                Err(ConcurrentSnowflakeError::PausedError)
+            ));
+        }
+
+        snowflake.resume();
+        assert!(!clone.is_paused());
+        let after = clone.gen().unwrap();
+        assert!(after > before);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_arc_handles_share_the_sequence() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = ConcurrentSnowflake::with_clock(1, UNIX_EPOCH, clock).unwrap();
+        let arc = snowflake.as_arc();
+        let weak = Arc::downgrade(&arc);
+        let mut from_arc = ConcurrentSnowflake::from_arc(arc);
+
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_000, 0, 1));
+        assert_eq!(decode(from_arc.gen().unwrap()), (1_000, 1, 1));
+        let inner = weak.upgrade().unwrap();
+        assert_eq!(decode(inner.lock().unwrap().gen().unwrap()), (1_000, 2, 1));
+
+        snowflake.pause();
+        assert!(!from_arc.is_paused());
+
+        drop((snowflake, from_arc, inner));
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reconfigure_applies_to_every_clone() {
+        let clock = MockClock::new(Duration::from_millis(5_000));
+        let mut snowflake = ConcurrentSnowflake::with_clock(0, UNIX_EPOCH, clock).unwrap();
+        let mut clone = snowflake.clone();
+        assert_eq!(decode(snowflake.gen().unwrap()), (5_000, 0, 0));
+
+        clone
+            .reconfigure(2, UNIX_EPOCH + Duration::from_millis(4_000))
+            .unwrap();
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_000, 0, 2));
+        assert_eq!(decode(clone.gen().unwrap()), (1_000, 1, 2));
+
+        assert!(matches!(
+            snowflake.reconfigure(4, UNIX_EPOCH),
+            Err(ConcurrentSnowflakeError::SnowflakeError(
+                SnowflakeError::InvalidServiceIdError {
+                    service_id: 4,
+                    max: 3
+                }
+            ))
+        ));
+        assert_eq!(decode(snowflake.gen().unwrap()), (1_000, 2, 2));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reconfigure_with_the_same_service_id_never_repeats_ids() {
+        let clock = MockClock::new(Duration::from_millis(5_000));
+        let epoch = UNIX_EPOCH + Duration::from_millis(1_000);
+        let mut snowflake = ConcurrentSnowflake::with_clock(0, epoch, clock.clone()).unwrap();
+        let mut ids = HashSet::new();
+        assert!(ids.insert(snowflake.gen().unwrap()));
+
+        // in the same millisecond
+        snowflake.reconfigure(0, epoch).unwrap();
+        let id = snowflake.gen().unwrap();
+        assert_eq!(decode(id), (4_000, 1, 0));
+        assert!(ids.insert(id));
+
+        assert!(matches!(
+            snowflake.reconfigure(0, epoch + Duration::from_millis(500)),
+            Err(ConcurrentSnowflakeError::SnowflakeError(
+                SnowflakeError::EpochMovedForwardError { service_id: 0, by }
+            )) if by == Duration::from_millis(500)
+        ));
+        let id = snowflake.gen().unwrap();
+        assert_eq!(decode(id), (4_000, 2, 0));
+        assert!(ids.insert(id));
+
+        // the last timestamp moves ahead with the epoch moving back
+        snowflake.reconfigure(0, UNIX_EPOCH).unwrap();
+        let id = snowflake.gen().unwrap();
+        assert_eq!(decode(id), (5_000, 3, 0));
+        assert!(ids.insert(id));
+        clock.advance(Duration::from_millis(1));
+        assert!(ids.insert(snowflake.gen().unwrap()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_set_wait_strategy_changes_how_exhaustion_waits() {
+        // 4 ids per millisecond, on a clock that only moves when slept on
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(0)
+            .layout(SnowflakeLayout::new(44, 2, 2).unwrap())
+            .clock(clock.clone())
+            .max_clock_stall(Duration::from_millis(2))
+            .build_concurrent()
+            .unwrap();
+        let clone = snowflake.clone();
+        for _ in 0..4 {
+            snowflake.gen().unwrap();
+        }
+        // sleeping moves the clock to the next millisecond
+        snowflake.gen().unwrap();
+        assert_eq!(clock.now(), Duration::from_millis(1_001));
+
+        // spinning waits for the clock to move on its own, which it never does
+        clone.set_wait_strategy(WaitStrategy::Spin).unwrap();
+        for _ in 0..3 {
+            snowflake.gen().unwrap();
+        }
+        assert!(matches!(
+            snowflake.gen(),
+            Err(ConcurrentSnowflakeError::SnowflakeError(
+                SnowflakeError::ClockStalledError { .. }
+            ))
+        ));
+        assert_eq!(clock.now(), Duration::from_millis(1_001));
+
+        clone.set_wait_strategy(WaitStrategy::Sleep).unwrap();
+        snowflake.gen().unwrap();
+        assert_eq!(clock.now(), Duration::from_millis(1_002));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_concurrent_exhaustion_with_mock_clock() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let snowflake = ConcurrentSnowflake::with_clock(0, UNIX_EPOCH, clock.clone()).unwrap();
+        let capacity = MAX_17_BITS as u64 + 1;
+
+        // only sleeping moves the clock, so 3 milliseconds' worth of ids spread over
+        // 4 threads always end in the third millisecond
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let mut snowflake = snowflake.clone();
+                std::thread::spawn(move || {
+                    (0..capacity * 3 / 4)
+                        .map(|_| snowflake.gen().unwrap())
+                        .collect::<Vec<i64>>()
+                })
+            })
+            .collect();
+        let mut ids: Vec<i64> = threads
+            .into_iter()
+            .flat_map(|thread| thread.join().unwrap())
+            .collect();
+
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len() as u64, capacity * 3);
+        assert_eq!(decode(ids[0]), (1_000, 0, 0));
+        assert_eq!(decode(*ids.last().unwrap()), (1_002, MAX_17_BITS, 0));
+        assert_eq!(clock.now(), Duration::from_millis(1_002));
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_snowflake_concurrently_creates_unique_positive_ids() {
         use std::thread::spawn;
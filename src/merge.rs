@@ -0,0 +1,111 @@
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::timestamp_bits_of;
+
+/*
+
+merges several id streams into one ordered by timestamp, e.g. the ids of
+different services, with a k-way merge that only ever holds one pending id per
+source
+
+each source must already be in non-decreasing timestamp order, as the ids of a
+single generator are, ids with the same timestamp come out in id order
+
+*/
+
+pub struct SnowflakeMerger<I> {
+    sources: Vec<I>,
+    // (timestamp, id, source index) of the next id of every non-empty source
+    heads: BinaryHeap<Reverse<(i64, i64, usize)>>,
+}
+
+impl<I: Iterator<Item = i64>> SnowflakeMerger<I> {
+    pub fn new(sources: impl IntoIterator<Item = I>) -> Self {
+        let mut merger = Self {
+            sources: sources.into_iter().collect(),
+            heads: BinaryHeap::new(),
+        };
+        for source in 0..merger.sources.len() {
+            merger.advance(source);
+        }
+        merger
+    }
+
+    fn advance(&mut self, source: usize) {
+        if let Some(id) = self.sources[source].next() {
+            self.heads
+                .push(Reverse((timestamp_bits_of(id), id, source)));
+        }
+    }
+}
+
+impl<I: Iterator<Item = i64>> Iterator for SnowflakeMerger<I> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        let Reverse((_, id, source)) = self.heads.pop()?;
+        self.advance(source);
+        Some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack;
+    #[cfg(feature = "std")]
+    use crate::Snowflake;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_merger_yields_timestamp_sorted_ids() {
+        let mut first = Snowflake::new(0).unwrap();
+        let mut second = Snowflake::new(1).unwrap();
+        let mut first_ids = Vec::new();
+        let mut second_ids = Vec::new();
+        for i in 0..10_000 {
+            if i % 3 == 0 {
+                second_ids.push(second.gen().unwrap());
+            } else {
+                first_ids.push(first.gen().unwrap());
+            }
+        }
+
+        let merged: Vec<i64> = SnowflakeMerger::new([
+            first_ids.clone().into_iter(),
+            second_ids.clone().into_iter(),
+        ])
+        .collect();
+        assert!(merged
+            .windows(2)
+            .all(|pair| timestamp_bits_of(pair[0]) <= timestamp_bits_of(pair[1])));
+
+        let mut expected = [first_ids, second_ids].concat();
+        let mut actual = merged;
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_merger_interleaves_by_timestamp() {
+        let first = vec![pack(1, 0, 0), pack(3, 0, 0), pack(5, 0, 0)];
+        let second = vec![pack(2, 0, 1), pack(3, 9, 1)];
+        let empty = vec![];
+        let merged: Vec<i64> =
+            SnowflakeMerger::new([first.into_iter(), empty.into_iter(), second.into_iter()])
+                .collect();
+        assert_eq!(
+            merged,
+            [
+                pack(1, 0, 0),
+                pack(2, 0, 1),
+                pack(3, 0, 0),
+                pack(3, 9, 1),
+                pack(5, 0, 0)
+            ]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{SnowflakeError, SnowflakeId};
+
+/*
+
+ids as 8 words to be read aloud or typed by humans, e.g. over a support call,
+where long decimal numbers get misheard
+
+every word stands for one of the 8 big-endian bytes of the id, so ids keep their
+order when words are compared by their position in the list, and as positive ids
+never set the top bit the first word is always from the first half of the list
+
+parsing ignores case and accepts words separated by whitespace or dashes
+
+*/
+
+// sorted, so parsing can binary search it
+const WORDS: [&str; 256] = [
+    "acid", "acorn", "actor", "adult", "agent", "alarm", "album", "alert", "alley", "alpha",
+    "amber", "angle", "ankle", "apple", "april", "arena", "argue", "armor", "arrow", "atlas",
+    "attic", "audio", "autumn", "bacon", "badge", "bagel", "baker", "bamboo", "banana", "banjo",
+    "barn", "basil", "basket", "beach", "beard", "beaver", "berry", "bicycle", "bison", "blade",
+    "blanket", "blossom", "board", "bonus", "border", "bottle", "boxer", "brain", "brick",
+    "bridge", "brush", "bubble", "bucket", "bugle", "button", "cabin", "cactus", "camel", "camera",
+    "canal", "candle", "canoe", "canyon", "carbon", "carpet", "carrot", "castle", "cattle",
+    "cello", "cement", "cereal", "chalk", "cherry", "chess", "cider", "cinema", "circus", "citrus",
+    "clock", "cloud", "clover", "coast", "cobra", "coffee", "comet", "copper", "coral", "cotton",
+    "cousin", "coyote", "crater", "crayon", "crystal", "cup", "curtain", "cycle", "dagger",
+    "daisy", "dancer", "delta", "denim", "desert", "diamond", "dinner", "disk", "doctor",
+    "dolphin", "donkey", "dragon", "drum", "eagle", "echo", "eclipse", "elbow", "ember", "engine",
+    "falcon", "fence", "ferry", "fiddle", "finger", "flag", "flute", "forest", "fossil", "fox",
+    "galaxy", "garden", "garlic", "gecko", "giant", "ginger", "glacier", "globe", "gold",
+    "gorilla", "grape", "gravel", "guitar", "hammer", "harbor", "harp", "hazel", "helmet", "hero",
+    "hockey", "honey", "hotel", "husky", "igloo", "indigo", "island", "ivory", "jacket", "jaguar",
+    "jelly", "jigsaw", "jungle", "kayak", "kettle", "kitten", "koala", "ladder", "lagoon",
+    "lantern", "laptop", "lemon", "lily", "lion", "lizard", "llama", "lobster", "magnet", "mango",
+    "maple", "marble", "meadow", "melon", "metal", "mirror", "monkey", "moose", "mosaic", "muffin",
+    "museum", "napkin", "nectar", "needle", "nickel", "noodle", "ocean", "olive", "onion",
+    "orange", "orbit", "orchid", "otter", "oyster", "paddle", "palace", "panda", "parrot",
+    "peanut", "pepper", "piano", "pickle", "pigeon", "pillow", "pilot", "pirate", "planet", "plum",
+    "pocket", "poem", "potato", "pumpkin", "puzzle", "quartz", "quilt", "rabbit", "radar", "radio",
+    "raven", "ribbon", "rocket", "saddle", "salmon", "sandal", "scarf", "shadow", "shell",
+    "silver", "sketch", "socket", "spider", "sponge", "squid", "statue", "sugar", "summer",
+    "sunset", "tablet", "tiger", "tomato", "tulip", "turtle", "velvet", "violin", "volcano",
+    "wagon", "walnut", "whale", "window", "wizard", "yacht", "zebra",
+];
+
+impl SnowflakeId {
+    pub fn to_mnemonic(self) -> String {
+        let words: Vec<&str> = self
+            .to_be_bytes()
+            .iter()
+            .map(|byte| WORDS[*byte as usize])
+            .collect();
+        words.join(" ")
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Result<Self, SnowflakeError> {
+        let error = || SnowflakeError::InvalidMnemonicError {
+            input: mnemonic.to_string(),
+        };
+        let mut bytes = [0; 8];
+        let mut words = mnemonic
+            .split(|c: char| c.is_whitespace() || c == '-')
+            .filter(|word| !word.is_empty());
+        for byte in &mut bytes {
+            let word = words.next().ok_or_else(error)?.to_ascii_lowercase();
+            *byte = WORDS.binary_search(&word.as_str()).map_err(|_| error())? as u8;
+        }
+        if words.next().is_some() {
+            return Err(error());
+        }
+        Self::from_be_bytes(bytes).map_err(|_| error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack;
+
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_from_mnemonic_is_the_inverse_of_to_mnemonic(id in 0..=i64::MAX) {
+            let id = SnowflakeId::new(id).unwrap();
+            prop_assert_eq!(SnowflakeId::from_mnemonic(&id.to_mnemonic()).unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn test_words_are_sorted_and_unique() {
+        assert!(WORDS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_mnemonic_of_an_id() {
+        let id = SnowflakeId::new(pack(1_000, 2, 3)).unwrap();
+        let mnemonic = id.to_mnemonic();
+        assert_eq!(mnemonic.split(' ').count(), 8);
+        assert_eq!(
+            SnowflakeId::new(0).unwrap().to_mnemonic(),
+            ["acid"; 8].join(" ")
+        );
+        assert_eq!(
+            SnowflakeId::from_mnemonic(&mnemonic.to_uppercase().replace(' ', "-")).unwrap(),
+            id
+        );
+        assert_eq!(
+            SnowflakeId::from_mnemonic(&format!("  {}\n", mnemonic.replace(' ', "  "))).unwrap(),
+            id
+        );
+    }
+
+    #[test]
+    fn test_from_mnemonic_rejects_invalid_mnemonics() {
+        let too_long = format!("{} acid", ["acid"; 8].join(" "));
+        for input in [
+            "",
+            "acid acid acid",
+            too_long.as_str(),
+            "acid acid acid acid acid acid acid notaword",
+            // the top bit of a positive id is never set
+            "zebra acid acid acid acid acid acid acid",
+        ] {
+            assert!(matches!(
+                SnowflakeId::from_mnemonic(input),
+                Err(SnowflakeError::InvalidMnemonicError { input: error_input }) if error_input == input
+            ));
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{service_id_of, DecodedSnowflake, Snowflake, SnowflakeError};
+
+/*
+
+a single generator that cycles through several service ids on successive gen
+calls, every service id keeps its own sequence, so the number of unique ids per
+millisecond is multiplied by the number of service ids
+
+every service id may have an epoch of its own, e.g. when services migrated to a
+new epoch at different times, so only the generator knows how to decode the
+timestamps of its ids
+
+*/
+
+#[derive(Debug)]
+pub struct MultiServiceSnowflake {
+    snowflakes: Vec<Snowflake>,
+    next: usize,
+}
+
+impl MultiServiceSnowflake {
+    pub fn new(service_ids: &[u16]) -> Result<Self, SnowflakeError> {
+        Self::with_epoch(service_ids, UNIX_EPOCH)
+    }
+
+    pub fn with_epoch(service_ids: &[u16], epoch: SystemTime) -> Result<Self, SnowflakeError> {
+        let services: Vec<(u16, SystemTime)> = service_ids
+            .iter()
+            .map(|service_id| (*service_id, epoch))
+            .collect();
+        Self::with_epochs(&services)
+    }
+
+    // every service id along with its own epoch
+    pub fn with_epochs(services: &[(u16, SystemTime)]) -> Result<Self, SnowflakeError> {
+        if services.is_empty() {
+            return Err(SnowflakeError::EmptyServiceIdsError);
+        }
+        for (i, (service_id, _)) in services.iter().enumerate() {
+            if services[..i].iter().any(|(other, _)| other == service_id) {
+                return Err(SnowflakeError::DuplicateServiceIdError {
+                    service_id: *service_id,
+                });
+            }
+        }
+        Ok(Self {
+            snowflakes: services
+                .iter()
+                .map(|(service_id, epoch)| Snowflake::with_epoch(*service_id, *epoch))
+                .collect::<Result<_, _>>()?,
+            next: 0,
+        })
+    }
+
+    // decodes an id with the epoch of its service id
+    pub fn decode(&self, id: i64) -> Result<DecodedSnowflake, SnowflakeError> {
+        let service_id = service_id_of(id);
+        self.snowflakes
+            .iter()
+            .find(|snowflake| snowflake.service_id() == service_id)
+            .ok_or(SnowflakeError::UnknownServiceIdError { service_id })?
+            .decode(id)
+    }
+
+    pub fn gen(&mut self) -> Result<i64, SnowflakeError> {
+        let id = self.snowflakes[self.next].gen()?;
+        self.next = (self.next + 1) % self.snowflakes.len();
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pack, timestamp_bits_of, MAX_17_BITS};
+    use std::time::Duration;
+
+    #[test]
+    fn test_multi_service_snowflake_cycles_through_service_ids() {
+        let mut snowflake = MultiServiceSnowflake::new(&[3, 1, 2]).unwrap();
+        let service_ids: Vec<u16> = (0..6)
+            .map(|_| service_id_of(snowflake.gen().unwrap()))
+            .collect();
+        assert_eq!(service_ids, [3, 1, 2, 3, 1, 2]);
+    }
+
+    #[test]
+    fn test_multi_service_snowflake_creates_unique_positive_ids() {
+        // twice the per millisecond ceiling of all 4 service ids combined
+        let num_ids = 2 * 4 * (MAX_17_BITS as usize + 1);
+        let mut snowflake = MultiServiceSnowflake::new(&[0, 1, 2, 3]).unwrap();
+        let mut ids: Vec<i64> = (0..num_ids).map(|_| snowflake.gen().unwrap()).collect();
+        ids.sort();
+        ids.dedup();
+        ids.retain(|id| *id > 0);
+        assert_eq!(ids.len(), num_ids);
+    }
+
+    #[test]
+    fn test_multi_service_snowflake_decodes_with_per_service_epochs() {
+        let first_epoch = UNIX_EPOCH + Duration::from_secs(1_000);
+        let second_epoch = UNIX_EPOCH + Duration::from_secs(2_000);
+        let mut snowflake =
+            MultiServiceSnowflake::with_epochs(&[(0, first_epoch), (1, second_epoch)]).unwrap();
+        let before = SystemTime::now();
+        let first = snowflake.gen().unwrap();
+        let second = snowflake.gen().unwrap();
+        let after = SystemTime::now();
+
+        for id in [first, second] {
+            let timestamp = snowflake.decode(id).unwrap().timestamp;
+            assert!(timestamp + Duration::from_millis(1) > before);
+            assert!(timestamp <= after);
+        }
+        // the same wall time is 1000 seconds fewer after the later epoch
+        let difference = timestamp_bits_of(first) - timestamp_bits_of(second);
+        assert!((1_000_000..=1_000_001).contains(&difference));
+
+        assert!(matches!(
+            snowflake.decode(pack(5, 0, 2)),
+            Err(SnowflakeError::UnknownServiceIdError { service_id: 2 })
+        ));
+        assert!(matches!(
+            MultiServiceSnowflake::with_epochs(&[(1, first_epoch), (1, second_epoch)]),
+            Err(SnowflakeError::DuplicateServiceIdError { service_id: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_multi_service_snowflake_rejects_invalid_service_ids() {
+        assert!(matches!(
+            MultiServiceSnowflake::new(&[]),
+            Err(SnowflakeError::EmptyServiceIdsError)
+        ));
+        assert!(matches!(
+            MultiServiceSnowflake::new(&[0, 1, 0]),
+            Err(SnowflakeError::DuplicateServiceIdError { service_id: 0 })
+        ));
+        assert!(matches!(
+            MultiServiceSnowflake::new(&[0, 4]),
+            Err(SnowflakeError::InvalidServiceIdError {
+                service_id: 4,
+                max: 3
+            })
+        ));
+    }
+}
@@ -0,0 +1,97 @@
+use core::num::NonZeroU32;
+use core::time::Duration;
+
+use crate::{Clock, Snowflake, SnowflakeError};
+
+/*
+
+a generator that hands out ids at a steady pace, e.g. to feed a downstream
+system that can't take bursts, every gen sleeps until at least min_interval has
+passed since the previous id, instead of failing or capping like a rate limiter
+
+the pace is kept on the generator's clock, so a MockClock makes it deterministic,
+and a caller slower than the pace is never made to catch up with a burst, the
+interval only ever counts from the previous id
+
+*/
+
+#[derive(Debug)]
+pub struct PacedSnowflake<C> {
+    snowflake: Snowflake<C>,
+    min_interval: Duration,
+    // clock time of the previous id
+    last: Option<Duration>,
+}
+
+impl<C: Clock> PacedSnowflake<C> {
+    pub fn new(snowflake: Snowflake<C>, min_interval: Duration) -> Self {
+        Self {
+            snowflake,
+            min_interval,
+            last: None,
+        }
+    }
+
+    pub fn with_rate(snowflake: Snowflake<C>, ids_per_second: NonZeroU32) -> Self {
+        Self::new(snowflake, Duration::from_secs(1) / ids_per_second.get())
+    }
+
+    pub fn gen(&mut self) -> Result<i64, SnowflakeError> {
+        let mut now = self.snowflake.clock.now();
+        if let Some(last) = self.last {
+            let next = last + self.min_interval;
+            if now < next {
+                self.snowflake.clock.sleep(next - now);
+                now = self.snowflake.clock.now().max(next);
+            }
+        }
+        let id = self.snowflake.gen()?;
+        self.last = Some(now);
+        Ok(id)
+    }
+
+    pub fn into_inner(self) -> Snowflake<C> {
+        self.snowflake
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{timestamp_bits_of, MockClock};
+    #[cfg(feature = "std")]
+    use std::time::Instant;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_paced_snowflake_keeps_the_rate() {
+        let snowflake = Snowflake::new(0).unwrap();
+        let mut paced = PacedSnowflake::with_rate(snowflake, NonZeroU32::new(1_000).unwrap());
+        let start = Instant::now();
+        let mut ids: Vec<i64> = (0..100).map(|_| paced.gen().unwrap()).collect();
+        let elapsed = start.elapsed();
+        // the first id doesn't wait for any before it
+        assert!(elapsed >= Duration::from_millis(99), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(200), "{elapsed:?}");
+        ids.dedup();
+        assert_eq!(ids.len(), 100);
+    }
+
+    #[test]
+    fn test_paced_snowflake_spaces_ids_on_its_clock() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let snowflake = Snowflake::builder(0).clock(clock.clone()).build().unwrap();
+        let mut paced = PacedSnowflake::new(snowflake, Duration::from_millis(10));
+        let first = paced.gen().unwrap();
+        let second = paced.gen().unwrap();
+        assert_eq!(timestamp_bits_of(first), 1_000);
+        assert_eq!(timestamp_bits_of(second), 1_010);
+
+        // time that already passed counts towards the interval
+        clock.advance(Duration::from_millis(4));
+        assert_eq!(timestamp_bits_of(paced.gen().unwrap()), 1_020);
+        clock.advance(Duration::from_millis(30));
+        assert_eq!(timestamp_bits_of(paced.gen().unwrap()), 1_050);
+        assert_eq!(timestamp_bits_of(paced.gen().unwrap()), 1_060);
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+use std::future::{ready, Future};
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use crate::{Clock, ConcurrentSnowflake};
+
+pub type IdProviderError = Box<dyn std::error::Error + Send + Sync>;
+
+pub type IdFuture<'a> = Pin<Box<dyn Future<Output = Result<i64, IdProviderError>> + Send + 'a>>;
+
+/*
+
+an id source for async code that can be shared as an Arc<dyn AsyncIdProvider>,
+so handlers don't depend on a concrete generator and tests can swap in a fake
+
+next_id returns a boxed future instead of being an async fn, since traits with
+async fns can't be used as trait objects
+
+*/
+pub trait AsyncIdProvider: Send + Sync {
+    fn next_id(&self) -> IdFuture<'_>;
+}
+
+/*
+
+generates synchronously, the future is ready right away but polling it blocks
+the executor thread for as long as gen waits, up to a millisecond whenever the
+sequence is exhausted and up to max_backward_skew when the clock went back, so
+async code should prefer an AsyncSnowflake, which waits on tokio's timer
+
+*/
+impl<C: Clock + Send> AsyncIdProvider for ConcurrentSnowflake<C> {
+    fn next_id(&self) -> IdFuture<'_> {
+        let id = self
+            .lock_unpaused()
+            .and_then(|mut snowflake| Ok(snowflake.gen()?));
+        Box::pin(ready(id.map_err(Into::into)))
+    }
+}
+
+// hands out a canned list of ids in order, then errors once they run out
+pub struct FakeIdProvider {
+    ids: Mutex<VecDeque<i64>>,
+}
+
+impl FakeIdProvider {
+    pub fn new(ids: impl IntoIterator<Item = i64>) -> Self {
+        Self {
+            ids: Mutex::new(ids.into_iter().collect()),
+        }
+    }
+}
+
+impl AsyncIdProvider for FakeIdProvider {
+    fn next_id(&self) -> IdFuture<'_> {
+        let id = self
+            .ids
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .pop_front()
+            .ok_or_else(|| FakeIdProviderExhaustedError.into());
+        Box::pin(ready(id))
+    }
+}
+
+#[derive(Debug)]
+pub struct FakeIdProviderExhaustedError;
+
+impl core::fmt::Display for FakeIdProviderExhaustedError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "fake id provider ran out of canned ids")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FakeIdProviderExhaustedError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConcurrentSnowflakeError;
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Waker};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    async fn handler(provider: Arc<dyn AsyncIdProvider>) -> Result<(i64, i64), IdProviderError> {
+        Ok((provider.next_id().await?, provider.next_id().await?))
+    }
+
+    #[test]
+    fn test_concurrent_snowflake_provides_unique_ids() {
+        let provider: Arc<dyn AsyncIdProvider> = Arc::new(ConcurrentSnowflake::new(0).unwrap());
+        let (first, second) = block_on(handler(provider)).unwrap();
+        assert!(first > 0);
+        assert!(second > first);
+    }
+
+    #[test]
+    fn test_paused_concurrent_snowflake_provides_no_ids() {
+        let snowflake = ConcurrentSnowflake::new(0).unwrap();
+        snowflake.pause();
+        let provider: Arc<dyn AsyncIdProvider> = Arc::new(snowflake.clone());
+        let error = block_on(provider.next_id()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(ConcurrentSnowflakeError::PausedError)
+        ));
+
+        snowflake.resume();
+        assert!(block_on(provider.next_id()).unwrap() > 0);
+    }
+
+    #[test]
+    fn test_fake_provider_returns_canned_ids_then_errors() {
+        let provider: Arc<dyn AsyncIdProvider> = Arc::new(FakeIdProvider::new([1, 2, 3]));
+        assert_eq!(block_on(handler(Arc::clone(&provider))).unwrap(), (1, 2));
+        assert_eq!(block_on(provider.next_id()).unwrap(), 3);
+        assert!(block_on(provider.next_id()).is_err());
+    }
+}
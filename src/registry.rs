@@ -0,0 +1,78 @@
+/*
+
+a development aid that catches two independent generators using the same
+service id in one process, which would make them generate colliding ids as each
+one keeps its own sequence, e.g. by creating a Snowflake per thread instead of
+sharing a ConcurrentSnowflake
+
+the check is off by default, since unrelated tests in the same process often
+create generators with the same service id on purpose, and it compiles to
+nothing in release builds, as well as without std
+
+*/
+
+#[cfg(all(debug_assertions, feature = "std"))]
+mod imp {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    static ENABLED: AtomicBool = AtomicBool::new(false);
+    static LIVE_SERVICE_IDS: Mutex<Vec<u16>> = Mutex::new(Vec::new());
+
+    pub fn set_duplicate_service_id_check(enabled: bool) {
+        ENABLED.store(enabled, Ordering::Relaxed);
+    }
+
+    #[derive(Debug)]
+    pub struct Registration(Option<u16>);
+
+    impl Registration {
+        pub fn new(service_id: u16) -> Self {
+            if !ENABLED.load(Ordering::Relaxed) {
+                return Self(None);
+            }
+            let mut live = LIVE_SERVICE_IDS
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let duplicate = live.contains(&service_id);
+            live.push(service_id);
+            drop(live);
+            let registration = Self(Some(service_id));
+            debug_assert!(
+                !duplicate,
+                "another live generator in this process already uses service id {service_id}"
+            );
+            registration
+        }
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            if let Some(service_id) = self.0 {
+                let mut live = LIVE_SERVICE_IDS
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                if let Some(i) = live.iter().position(|live| *live == service_id) {
+                    live.swap_remove(i);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(all(debug_assertions, feature = "std")))]
+mod imp {
+    pub fn set_duplicate_service_id_check(_enabled: bool) {}
+
+    #[derive(Debug)]
+    pub struct Registration;
+
+    impl Registration {
+        pub fn new(_service_id: u16) -> Self {
+            Self
+        }
+    }
+}
+
+pub use imp::set_duplicate_service_id_check;
+pub(crate) use imp::Registration;
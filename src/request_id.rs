@@ -0,0 +1,43 @@
+use http::{HeaderValue, Request};
+use tower_http::request_id::{MakeRequestId, RequestId};
+
+use crate::{Clock, ConcurrentSnowflake};
+
+/*
+
+lets a ConcurrentSnowflake mint the request ids of tower-http's request id
+middleware, e.g. SetRequestIdLayer::x_request_id(snowflake), which is shared by
+every clone the middleware makes of it
+
+a request gets no id when generating one fails, as the middleware can't report
+errors
+
+*/
+impl<C: Clock> MakeRequestId for ConcurrentSnowflake<C> {
+    fn make_request_id<B>(&mut self, _request: &Request<B>) -> Option<RequestId> {
+        let id = self.gen().ok()?;
+        Some(RequestId::new(HeaderValue::from(id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, MockClock};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_request_ids_are_generated_ids() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = ConcurrentSnowflake::with_clock(1, UNIX_EPOCH, clock).unwrap();
+        let request = Request::new(());
+        for seq in 0..3 {
+            let request_id = snowflake.make_request_id(&request).unwrap();
+            let id: i64 = request_id.header_value().to_str().unwrap().parse().unwrap();
+            assert_eq!(decode(id), (1_000, seq, 1));
+        }
+
+        snowflake.pause();
+        assert!(snowflake.make_request_id(&request).is_none());
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+/*
+
+ids as decimal strings, for json read by javascript, where every number is a
+double and ids beyond 2^53 silently lose their lowest bits, as ids of the
+default layout are from about 200 days after their epoch on, use it with serde's
+with attribute on i64 and SnowflakeId fields, e.g.
+
+    #[derive(Serialize, Deserialize)]
+    struct User {
+        #[serde(with = "snowflake::serde_string")]
+        id: SnowflakeId,
+    }
+
+reading only accepts strings, not numbers, and a SnowflakeId still rejects
+negative ids
+
+*/
+
+pub fn serialize<T: Display, S: Serializer>(id: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(id)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    let input = String::deserialize(deserializer)?;
+    input.parse().map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{pack, SnowflakeId};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Row {
+        #[serde(with = "crate::serde_string")]
+        id: SnowflakeId,
+        #[serde(with = "crate::serde_string")]
+        parent: i64,
+    }
+
+    #[test]
+    fn test_ids_round_trip_as_strings() {
+        let row = Row {
+            id: SnowflakeId::new(pack(1_000, 5, 2)).unwrap(),
+            parent: pack(900, 0, 1),
+        };
+        let json = serde_json::to_string(&row).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"id":"{}","parent":"{}"}}"#,
+                pack(1_000, 5, 2),
+                pack(900, 0, 1)
+            )
+        );
+        assert_eq!(serde_json::from_str::<Row>(&json).unwrap(), row);
+    }
+
+    #[test]
+    fn test_only_strings_of_valid_ids_are_read() {
+        for json in [
+            r#"{"id":5,"parent":"1"}"#,
+            r#"{"id":"-5","parent":"1"}"#,
+            r#"{"id":"five","parent":"1"}"#,
+            r#"{"id":"5","parent":"9223372036854775808"}"#,
+        ] {
+            assert!(serde_json::from_str::<Row>(json).is_err(), "{json}");
+        }
+    }
+}
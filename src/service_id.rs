@@ -0,0 +1,190 @@
+use std::net::IpAddr;
+
+use crate::{Snowflake, SnowflakeError, SnowflakeLayout};
+
+/*
+
+generators that work out their service id from where they run, so replicas
+don't each need one wired in by hand
+
+    from_env      the decimal value of an environment variable, e.g. one set from
+                  a pod's ordinal or a deployment's replica index
+    from_hostname the number the hostname ends in, e.g. 3 for web-3 of a
+                  kubernetes statefulset, ignoring any domain after the first dot
+    from_ip       the low bits of an ip address, e.g. the pod ip, which only stays
+                  unique if the replicas share a subnet no larger than the layout
+                  has service ids for
+
+a value that doesn't fit the layout's service id bits is a DerivedServiceIdError
+rather than wrapping around into another replica's service id, except for
+from_ip, which takes only as many bits as fit
+
+*/
+
+impl Snowflake {
+    pub fn from_env(var: &str, layout: SnowflakeLayout) -> Result<Self, SnowflakeError> {
+        let value = std::env::var(var).map_err(|_| SnowflakeError::ServiceIdSourceError {
+            source: format!("environment variable {var}"),
+        })?;
+        Self::from_env_value(var, &value, layout)
+    }
+
+    // from_env once the variable is read, apart so tests don't have to set it
+    fn from_env_value(
+        var: &str,
+        value: &str,
+        layout: SnowflakeLayout,
+    ) -> Result<Self, SnowflakeError> {
+        let source = format!("environment variable {var}");
+        let service_id =
+            value
+                .trim()
+                .parse()
+                .map_err(|_| SnowflakeError::ServiceIdSourceError {
+                    source: format!("{source} = {value:?}"),
+                })?;
+        Self::with_derived_service_id(service_id, source, layout)
+    }
+
+    pub fn from_hostname(layout: SnowflakeLayout) -> Result<Self, SnowflakeError> {
+        let hostname = hostname().ok_or_else(|| SnowflakeError::ServiceIdSourceError {
+            source: "hostname".to_string(),
+        })?;
+        let source = format!("hostname {hostname:?}");
+        let service_id =
+            ordinal_of(&hostname).ok_or_else(|| SnowflakeError::ServiceIdSourceError {
+                source: source.clone(),
+            })?;
+        Self::with_derived_service_id(service_id, source, layout)
+    }
+
+    pub fn from_ip(ip: IpAddr, layout: SnowflakeLayout) -> Result<Self, SnowflakeError> {
+        let bits = match ip {
+            IpAddr::V4(ip) => u32::from(ip) as u128,
+            IpAddr::V6(ip) => u128::from(ip),
+        };
+        let service_id = (bits & layout.max_service_id() as u128) as u16;
+        Self::with_layout(service_id, layout)
+    }
+
+    fn with_derived_service_id(
+        value: u64,
+        source: String,
+        layout: SnowflakeLayout,
+    ) -> Result<Self, SnowflakeError> {
+        let max = layout.max_service_id();
+        if value > max as u64 {
+            return Err(SnowflakeError::DerivedServiceIdError { source, value, max });
+        }
+        Self::with_layout(value as u16, layout)
+    }
+}
+
+// the number at the end of the first label of a hostname, if it ends in digits
+fn ordinal_of(hostname: &str) -> Option<u64> {
+    let label = hostname.split('.').next()?;
+    let digits = label.len() - label.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    label[label.len() - digits..].parse().ok()
+}
+
+#[cfg(target_os = "linux")]
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: buf is valid for writes of its whole length
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if result != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0)?;
+    String::from_utf8(buf[..len].to_vec())
+        .ok()
+        .filter(|hostname| !hostname.is_empty())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn hostname() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .filter(|hostname| !hostname.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn test_from_env_parses_the_service_id() {
+        let layout = SnowflakeLayout::DEFAULT;
+        let snowflake = Snowflake::from_env_value("SERVICE_ID", " 3\n", layout);
+        assert_eq!(snowflake.unwrap().service_id(), 3);
+
+        assert!(matches!(
+            Snowflake::from_env_value("SERVICE_ID", "4", layout),
+            Err(SnowflakeError::DerivedServiceIdError {
+                value: 4,
+                max: 3,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Snowflake::from_env_value("SERVICE_ID", "web", layout),
+            Err(SnowflakeError::ServiceIdSourceError { source })
+                if source == r#"environment variable SERVICE_ID = "web""#
+        ));
+    }
+
+    #[test]
+    fn test_from_env_reports_an_unset_variable() {
+        assert!(matches!(
+            Snowflake::from_env("SNOWFLAKE_TEST_FROM_ENV_UNSET", SnowflakeLayout::DEFAULT),
+            Err(SnowflakeError::ServiceIdSourceError { source })
+                if source == "environment variable SNOWFLAKE_TEST_FROM_ENV_UNSET"
+        ));
+    }
+
+    #[test]
+    fn test_ordinal_of_hostnames() {
+        assert_eq!(ordinal_of("web-3"), Some(3));
+        assert_eq!(ordinal_of("web-12.web.default.svc.cluster.local"), Some(12));
+        assert_eq!(ordinal_of("db2"), Some(2));
+        assert_eq!(ordinal_of("web"), None);
+        assert_eq!(ordinal_of("web-3a"), None);
+        assert_eq!(ordinal_of("host.10"), None);
+        assert_eq!(ordinal_of("99999999999999999999999"), None);
+    }
+
+    #[test]
+    fn test_from_hostname_uses_the_hostname_ordinal() {
+        let layout = SnowflakeLayout::DISCORD;
+        match (
+            hostname().as_deref().and_then(ordinal_of),
+            Snowflake::from_hostname(layout),
+        ) {
+            (Some(ordinal), Ok(snowflake)) => assert_eq!(snowflake.service_id() as u64, ordinal),
+            (Some(_), Err(error)) => {
+                assert!(matches!(
+                    error,
+                    SnowflakeError::DerivedServiceIdError { .. }
+                ))
+            }
+            (None, result) => assert!(matches!(
+                result,
+                Err(SnowflakeError::ServiceIdSourceError { .. })
+            )),
+        }
+    }
+
+    #[test]
+    fn test_from_ip_takes_the_low_bits() {
+        let layout = SnowflakeLayout::DISCORD;
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 6, 7));
+        let snowflake = Snowflake::from_ip(ip, layout).unwrap();
+        assert_eq!(snowflake.service_id(), (6 << 8 | 7) & 0x3ff);
+
+        let ip = IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 0xabcd));
+        let snowflake = Snowflake::from_ip(ip, layout).unwrap();
+        assert_eq!(snowflake.service_id(), 0xabcd & 0x3ff);
+    }
+}
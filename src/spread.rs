@@ -0,0 +1,106 @@
+use crate::{pack, sequence_of, service_id_of, timestamp_bits_of};
+
+/*
+
+transforms that scatter ids across the key space for databases that shard by
+key prefix, where the always increasing timestamp prefix of plain ids sends
+every write to the same shard
+
+both transforms only move bits around, so spread ids stay positive and unique,
+and applying the same transform again gives back the original id
+
+spread ids are NOT sortable by creation time anymore, so only use them where
+write distribution matters more than time ordering, and always revert them
+before decoding
+
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spread {
+    // reverses the bits of the timestamp, so the fastest changing low bits of the
+    // timestamp become the prefix of the id
+    BitReverse,
+    // xors the top bits of the id with a hash of the rest of it, bits is clamped
+    // between 1 and 44 so only timestamp bits ever get changed
+    HashPrefix { bits: u32 },
+}
+
+impl Spread {
+    pub const fn apply(self, id: i64) -> i64 {
+        match self {
+            Self::BitReverse => {
+                let reversed = (timestamp_bits_of(id) as u64).reverse_bits() >> (64 - 44);
+                pack(reversed as i64, sequence_of(id), service_id_of(id))
+            }
+            Self::HashPrefix { bits } => {
+                let bits = if bits < 1 {
+                    1
+                } else if bits > 44 {
+                    44
+                } else {
+                    bits
+                };
+                let rest = id as u64 & (u64::MAX >> (bits + 1));
+                let prefix = mix(rest) >> (64 - bits);
+                id ^ (prefix << (63 - bits)) as i64
+            }
+        }
+    }
+
+    // same as apply, since both transforms are their own inverse
+    pub const fn revert(self, spread: i64) -> i64 {
+        self.apply(spread)
+    }
+}
+
+// splitmix64 finalizer
+const fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    const SPREADS: [Spread; 4] = [
+        Spread::BitReverse,
+        Spread::HashPrefix { bits: 1 },
+        Spread::HashPrefix { bits: 8 },
+        Spread::HashPrefix { bits: 44 },
+    ];
+
+    proptest! {
+        #[test]
+        fn test_revert_is_the_inverse_of_apply(id in 0..=i64::MAX) {
+            for spread in SPREADS {
+                let spread_id = spread.apply(id);
+                prop_assert!(spread_id >= 0);
+                prop_assert_eq!(spread.revert(spread_id), id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_consecutive_milliseconds_get_different_prefixes() {
+        for spread in [Spread::BitReverse, Spread::HashPrefix { bits: 8 }] {
+            let mut prefixes: Vec<i64> = (1_000..1_016)
+                .map(|millis| spread.apply(pack(millis, 0, 0)) >> 55)
+                .collect();
+            prefixes.sort();
+            prefixes.dedup();
+            assert!(prefixes.len() > 8, "{spread:?} prefixes: {prefixes:?}");
+        }
+    }
+
+    #[test]
+    fn test_hash_prefix_only_changes_timestamp_bits() {
+        let id = pack(123_456_789, 42, 3);
+        let spread_id = Spread::HashPrefix { bits: 64 }.apply(id);
+        assert_eq!(sequence_of(spread_id), 42);
+        assert_eq!(service_id_of(spread_id), 3);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::Snowflake;
+#[cfg(feature = "serde")]
+use crate::SnowflakeError;
+
+/*
+
+a snapshot of where a generator is in its sequence, to persist between runs and
+restore with SnowflakeBuilder::restore, so a restarted generator carries on
+right after the last id it generated, just like Snowflake::resume_from, but also
+keeping a random sequence start
+
+with the serde feature states also read and write themselves as json, e.g.
+{"service_id":1,"last_millis":1000,"seq":3,"seq_offset":0}
+
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnowflakeState {
+    // the service id stored in ids, without any offset
+    pub service_id: u16,
+    // the timestamp field of the last id, unsigned so a negative one is never read
+    pub last_millis: u64,
+    // how many ids were generated in that millisecond
+    pub seq: u32,
+    // where the sequence started in that millisecond
+    pub seq_offset: u32,
+}
+
+#[cfg(feature = "serde")]
+impl SnowflakeState {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("serializing a state never fails")
+    }
+
+    // only checks the json is a state, restoring it checks it fits the layout
+    pub fn from_json(json: &str) -> Result<Self, SnowflakeError> {
+        serde_json::from_str(json).map_err(|_| SnowflakeError::InvalidStateJsonError {
+            input: json.to_string(),
+        })
+    }
+}
+
+impl<C> Snowflake<C> {
+    pub fn state(&self) -> SnowflakeState {
+        SnowflakeState {
+            service_id: self.service_id,
+            last_millis: self.last_millis as u64,
+            seq: self.seq,
+            seq_offset: self.seq_offset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::SnowflakeError;
+    use crate::{decode, MockClock};
+    use std::time::Duration;
+
+    #[test]
+    fn test_restored_state_carries_on_after_the_last_id() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1).clock(clock.clone()).build().unwrap();
+        for _ in 0..3 {
+            snowflake.gen().unwrap();
+        }
+        let state = snowflake.state();
+        assert_eq!(
+            state,
+            SnowflakeState {
+                service_id: 1,
+                last_millis: 1_000,
+                seq: 3,
+                seq_offset: 0
+            }
+        );
+        drop(snowflake);
+
+        let mut restored = Snowflake::builder(1)
+            .clock(clock)
+            .restore(state)
+            .build()
+            .unwrap();
+        assert_eq!(decode(restored.gen().unwrap()), (1_000, 3, 1));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_restore_rejects_states_that_dont_fit() {
+        let state = SnowflakeState {
+            service_id: 1,
+            last_millis: 1_000,
+            seq: 0,
+            seq_offset: 0,
+        };
+        assert!(matches!(
+            Snowflake::builder(2).restore(state).build(),
+            Err(SnowflakeError::ServiceIdMismatchError {
+                expected: 2,
+                found: 1
+            })
+        ));
+        let too_late = SnowflakeState {
+            last_millis: 1 << 44,
+            ..state
+        };
+        assert!(matches!(
+            Snowflake::builder(1).restore(too_late).build(),
+            Err(SnowflakeError::InvalidTimestampError { .. })
+        ));
+        // an exhausted millisecond is a valid state, one beyond isn't
+        let exhausted = SnowflakeState {
+            seq: 1 << 17,
+            ..state
+        };
+        assert!(Snowflake::builder(1).restore(exhausted).build().is_ok());
+        let beyond = SnowflakeState {
+            seq: (1 << 17) + 1,
+            ..state
+        };
+        assert!(matches!(
+            Snowflake::builder(1).restore(beyond).build(),
+            Err(SnowflakeError::InvalidSequenceError { .. })
+        ));
+        let offset = SnowflakeState {
+            seq_offset: 1 << 17,
+            ..state
+        };
+        assert!(matches!(
+            Snowflake::builder(1).restore(offset).build(),
+            Err(SnowflakeError::InvalidSequenceError { .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_state_round_trips_through_json() {
+        let state = SnowflakeState {
+            service_id: 1,
+            last_millis: 1_000,
+            seq: 3,
+            seq_offset: 7,
+        };
+        let json = state.to_json();
+        assert_eq!(
+            json,
+            r#"{"service_id":1,"last_millis":1000,"seq":3,"seq_offset":7}"#
+        );
+        assert_eq!(SnowflakeState::from_json(&json).unwrap(), state);
+
+        for corrupt in [
+            "",
+            "{}",
+            r#"{"service_id":1,"last_millis":-1000,"seq":3,"seq_offset":7}"#,
+            r#"{"service_id":1,"last_millis":"1000","seq":3,"seq_offset":7}"#,
+        ] {
+            assert!(matches!(
+                SnowflakeState::from_json(corrupt),
+                Err(SnowflakeError::InvalidStateJsonError { input }) if input == corrupt
+            ));
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::Snowflake;
+#[cfg(feature = "std")]
+use crate::{Clock, ConcurrentSnowflake, ConcurrentSnowflakeError};
+
+/*
+
+counters of what a generator went through since it was built, for capacity
+planning, e.g. rollovers growing with ids_generated mean bursts regularly use up
+a millisecond's sequence
+
+    ids_generated      every id generated, like the ordinal of gen_with_ordinal
+    sequence_rollovers times the sequence of a millisecond ran out, whether gen
+                       then waited or borrowed the next millisecond
+    millis_waited      milliseconds of the clock spent waiting, for the next
+                       millisecond or for a clock that went back to catch up
+    clock_regressions  gen calls that found the clock behind the last id, except
+                       by what BorrowTimestamp allows
+
+they are always counted, as all but ids_generated only change on the slow path
+of gen, which the common case of a millisecond with sequence to spare never
+takes, the waits an AsyncSnowflake makes before calling gen aren't counted
+
+*/
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SnowflakeStats {
+    pub ids_generated: u64,
+    pub sequence_rollovers: u64,
+    pub millis_waited: u64,
+    pub clock_regressions: u64,
+}
+
+impl<C> Snowflake<C> {
+    pub fn stats(&self) -> SnowflakeStats {
+        SnowflakeStats {
+            ids_generated: self.generated,
+            sequence_rollovers: self.sequence_rollovers,
+            millis_waited: self.millis_waited,
+            clock_regressions: self.clock_regressions,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: Clock> ConcurrentSnowflake<C> {
+    pub fn stats(&self) -> Result<SnowflakeStats, ConcurrentSnowflakeError> {
+        Ok(self.lock()?.stats())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::ExhaustionPolicy;
+    use crate::{MockClock, SnowflakeLayout};
+    use std::time::Duration;
+
+    #[test]
+    fn test_stats_count_rollovers_waits_and_regressions() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(0)
+            .clock(clock.clone())
+            .layout(SnowflakeLayout::new(44, 2, 2).unwrap())
+            .max_backward_skew(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        for _ in 0..4 {
+            snowflake.gen().unwrap();
+        }
+        assert_eq!(
+            snowflake.stats(),
+            SnowflakeStats {
+                ids_generated: 4,
+                ..SnowflakeStats::default()
+            }
+        );
+
+        // the 5th id waits for the next millisecond
+        snowflake.gen().unwrap();
+        clock.set(Duration::from_millis(997));
+        // and this one for the clock to get back to it
+        snowflake.gen().unwrap();
+        assert_eq!(
+            snowflake.stats(),
+            SnowflakeStats {
+                ids_generated: 6,
+                sequence_rollovers: 1,
+                millis_waited: 5,
+                clock_regressions: 1,
+            }
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_borrowed_milliseconds_roll_over_without_waiting() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(0)
+            .clock(clock)
+            .layout(SnowflakeLayout::new(44, 2, 2).unwrap())
+            .exhaustion_policy(ExhaustionPolicy::BorrowTimestamp {
+                max_drift: Duration::from_millis(10),
+            })
+            .build_concurrent()
+            .unwrap();
+        for _ in 0..12 {
+            snowflake.gen().unwrap();
+        }
+        let stats = snowflake.stats().unwrap();
+        assert_eq!(stats.ids_generated, 12);
+        assert_eq!(stats.sequence_rollovers, 2);
+        assert_eq!(stats.millis_waited, 0);
+        assert_eq!(stats.clock_regressions, 0);
+    }
+}
@@ -0,0 +1,112 @@
+use tracing::{event, Level};
+
+// events need their level at compile time, so every level gets its own callsite
+pub(crate) fn gen_event(level: Level, millis: i64, seq: u32, service_id: u16, waited: bool) {
+    macro_rules! gen_event {
+        ($level:expr) => {
+            event!(
+                $level,
+                timestamp = millis,
+                sequence = seq,
+                service_id,
+                waited,
+                "generated snowflake id"
+            )
+        };
+    }
+
+    if level == Level::ERROR {
+        gen_event!(Level::ERROR);
+    } else if level == Level::WARN {
+        gen_event!(Level::WARN);
+    } else if level == Level::INFO {
+        gen_event!(Level::INFO);
+    } else if level == Level::DEBUG {
+        gen_event!(Level::DEBUG);
+    } else {
+        gen_event!(Level::TRACE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use super::*;
+    use crate::{MockClock, Snowflake, SnowflakeLayout};
+
+    // records the level and fields of every event as strings
+    #[derive(Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<(Level, String)>>>);
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.push_str(&format!("{}={:?} ", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(String::new());
+            event.record(&mut fields);
+            let level = *event.metadata().level();
+            self.0
+                .lock()
+                .unwrap()
+                .push((level, fields.0.trim_end().to_string()));
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn test_gen_emits_an_event_per_id() {
+        let recorder = Recorder::default();
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1)
+            .clock(clock)
+            .layout(SnowflakeLayout::new(44, 1, 2).unwrap())
+            .trace_level(Level::INFO)
+            .build()
+            .unwrap();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            for _ in 0..3 {
+                snowflake.gen().unwrap();
+            }
+        });
+
+        let events = recorder.0.lock().unwrap();
+        let fields: Vec<&str> = events.iter().map(|(_, fields)| fields.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "message=generated snowflake id timestamp=1000 sequence=0 service_id=1 waited=false",
+                "message=generated snowflake id timestamp=1000 sequence=1 service_id=1 waited=false",
+                "message=generated snowflake id timestamp=1001 sequence=0 service_id=1 waited=true",
+            ]
+        );
+        assert!(events.iter().all(|(level, _)| *level == Level::INFO));
+    }
+}
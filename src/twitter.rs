@@ -0,0 +1,109 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{Clock, Snowflake, SnowflakeBuilder, SnowflakeError, SnowflakeLayout};
+
+/*
+
+interop with twitter snowflakes, the original ones, which are passed around as
+decimal strings and count milliseconds since twitter's epoch in late 2010
+
+    timestamp (41 bits) | datacenter id (5 bits) | worker id (5 bits) | sequence (12 bits)
+
+this is the same layout as discord's, with another epoch, generators built with
+Snowflake::twitter_builder mint ids in this format, with the service id as
+datacenter << 5 | worker
+
+*/
+
+pub const TWITTER_EPOCH: Duration = Duration::from_millis(1_288_834_974_657);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TwitterId {
+    pub timestamp: SystemTime,
+    pub datacenter_id: u8,
+    pub worker_id: u8,
+    pub sequence: u16,
+}
+
+// parses and decodes the decimal text of any twitter id, e.g. the id_str of a tweet
+pub fn decode_twitter(id: &str) -> Result<TwitterId, SnowflakeError> {
+    let parsed: u64 = id
+        .parse()
+        .map_err(|_| SnowflakeError::InvalidIdStringError {
+            input: id.to_string(),
+        })?;
+    Ok(TwitterId {
+        timestamp: UNIX_EPOCH + TWITTER_EPOCH + Duration::from_millis(parsed >> 22),
+        datacenter_id: (parsed >> 17 & 0x1f) as u8,
+        worker_id: (parsed >> 12 & 0x1f) as u8,
+        sequence: (parsed & 0xfff) as u16,
+    })
+}
+
+impl Snowflake {
+    // a builder with the twitter layout and epoch, service_id is datacenter << 5 | worker
+    pub fn twitter_builder(service_id: u16) -> SnowflakeBuilder {
+        Self::builder(service_id)
+            .layout(SnowflakeLayout::TWITTER)
+            .epoch(UNIX_EPOCH + TWITTER_EPOCH)
+    }
+}
+
+impl<C: Clock> Snowflake<C> {
+    // generates an id as the decimal text twitter apis use for id_str, meant for
+    // generators built with twitter_builder
+    pub fn gen_twitter_string(&mut self) -> Result<String, SnowflakeError> {
+        Ok(self.gen()?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockClock;
+
+    #[test]
+    fn test_decode_twitter_id() {
+        // a tweet from the twitter api docs, created at 2018-10-10T20:19:24Z
+        let id = decode_twitter("1050118621198921728").unwrap();
+        assert_eq!(
+            id,
+            TwitterId {
+                timestamp: UNIX_EPOCH + Duration::from_millis(1_539_202_764_211),
+                datacenter_id: 10,
+                worker_id: 27,
+                sequence: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_twitter_rejects_malformed_ids() {
+        for id in ["", "-1", "12a", "18446744073709551616"] {
+            assert!(matches!(
+                decode_twitter(id),
+                Err(SnowflakeError::InvalidIdStringError { input }) if input == id
+            ));
+        }
+    }
+
+    #[test]
+    fn test_gen_twitter_string_round_trips() {
+        let now = TWITTER_EPOCH + Duration::from_millis(250_367_789_554);
+        let mut snowflake = Snowflake::twitter_builder(10 << 5 | 27)
+            .clock(MockClock::new(now))
+            .build()
+            .unwrap();
+        snowflake.gen().unwrap();
+        let id = snowflake.gen_twitter_string().unwrap();
+        assert_eq!(
+            decode_twitter(&id).unwrap(),
+            TwitterId {
+                timestamp: UNIX_EPOCH + now,
+                datacenter_id: 10,
+                worker_id: 27,
+                sequence: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, BufRead};
+
+use crate::Snowflake;
+
+/*
+
+audits a dump of decimal ids, one per line, for duplicates, for ids whose
+timestamp is earlier than the one of the id before them, and for lines that
+aren't valid ids of the generator's layout, without loading the whole dump
+
+only the last VERIFY_WINDOW ids are remembered, so a duplicate is reported if
+at most that many ids separate it from the id it repeats, which covers dumps in
+generation order, where duplicates land in the same or adjacent milliseconds
+
+surrounding whitespace and blank lines are skipped, every other line counts,
+and like in decode_lines line numbers start at 1
+
+*/
+
+pub const VERIFY_WINDOW: usize = 65_536;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    // number of ids checked, including invalid ones
+    pub ids: u64,
+    // line numbers of ids repeating an earlier one, along with the id
+    pub duplicates: Vec<(usize, i64)>,
+    // line numbers of ids with an earlier timestamp than the valid id before them
+    pub out_of_order: Vec<usize>,
+    // line numbers that aren't a decimal id, or hold a negative id or one beyond
+    // the layout's max_id
+    pub invalid: Vec<usize>,
+}
+
+impl VerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.duplicates.is_empty() && self.out_of_order.is_empty() && self.invalid.is_empty()
+    }
+}
+
+impl<C> Snowflake<C> {
+    pub fn verify_stream(&self, reader: impl BufRead) -> io::Result<VerifyReport> {
+        let mut report = VerifyReport::default();
+        let mut recent = VecDeque::with_capacity(VERIFY_WINDOW);
+        let mut seen = HashSet::with_capacity(VERIFY_WINDOW);
+        let mut last_timestamp = None;
+        for (i, line) in reader.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line?;
+            let input = line.trim();
+            if input.is_empty() {
+                continue;
+            }
+            report.ids += 1;
+            let id = match input.parse::<i64>() {
+                Ok(id) if id >= 0 && id <= self.layout.max_id() => id,
+                _ => {
+                    report.invalid.push(line_number);
+                    continue;
+                }
+            };
+
+            let timestamp = self.layout.timestamp_bits_of(id);
+            if last_timestamp.is_some_and(|last| timestamp < last) {
+                report.out_of_order.push(line_number);
+            }
+            last_timestamp = Some(timestamp);
+
+            if !seen.insert(id) {
+                report.duplicates.push((line_number, id));
+                continue;
+            }
+            if recent.len() == VERIFY_WINDOW {
+                if let Some(oldest) = recent.pop_front() {
+                    seen.remove(&oldest);
+                }
+            }
+            recent.push_back(id);
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pack, MockClock};
+    use std::fmt::Write;
+    use std::time::Duration;
+
+    #[test]
+    fn test_verify_stream_finds_an_injected_duplicate() {
+        let clock = MockClock::new(Duration::from_millis(1_000));
+        let mut snowflake = Snowflake::builder(1).clock(clock.clone()).build().unwrap();
+        let mut ids = Vec::new();
+        for _ in 0..5 {
+            snowflake.append_n(&mut ids, 100).unwrap();
+            clock.advance(Duration::from_millis(1));
+        }
+        let clean: String = ids.iter().map(|id| format!("{id}\n")).collect();
+        assert!(snowflake
+            .verify_stream(clean.as_bytes())
+            .unwrap()
+            .is_clean());
+
+        let mut dump = String::new();
+        for (i, id) in ids.iter().enumerate() {
+            writeln!(dump, "{id}").unwrap();
+            if i == 250 {
+                writeln!(dump, "{}", ids[249]).unwrap();
+            }
+        }
+        let report = snowflake.verify_stream(dump.as_bytes()).unwrap();
+        assert_eq!(report.ids, 501);
+        assert_eq!(report.duplicates, [(252, ids[249])]);
+        assert!(report.out_of_order.is_empty());
+        assert!(report.invalid.is_empty());
+    }
+
+    #[test]
+    fn test_verify_stream_reports_disorder_and_invalid_lines() {
+        let snowflake = Snowflake::new(1).unwrap();
+        let dump = format!(
+            "{}\n\n{}\nnot an id\n-5\n9223372036854775808\n{}\n",
+            pack(1_000, 0, 1),
+            pack(2_000, 0, 1),
+            pack(1_500, 0, 1)
+        );
+        let report = snowflake.verify_stream(dump.as_bytes()).unwrap();
+        assert_eq!(
+            report,
+            VerifyReport {
+                ids: 6,
+                duplicates: Vec::new(),
+                out_of_order: vec![7],
+                invalid: vec![4, 5, 6],
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_stream_forgets_ids_beyond_the_window() {
+        let snowflake = Snowflake::new(1).unwrap();
+        let mut dump = String::new();
+        for seq in 0..=VERIFY_WINDOW as u32 {
+            writeln!(dump, "{}", pack(1_000, seq, 1)).unwrap();
+        }
+        // the first id fell out of the window, the last one is still in it
+        writeln!(dump, "{}", pack(1_000, 0, 1)).unwrap();
+        writeln!(dump, "{}", pack(1_000, VERIFY_WINDOW as u32, 1)).unwrap();
+        let report = snowflake.verify_stream(dump.as_bytes()).unwrap();
+        assert_eq!(
+            report.duplicates,
+            [(VERIFY_WINDOW + 3, pack(1_000, VERIFY_WINDOW as u32, 1))]
+        );
+    }
+}
@@ -0,0 +1,29 @@
+// a test binary of its own, since enabling the check affects every generator in its
+// process, the tests in it share that process and its registry, so each one uses
+// service ids none of the others do
+#![cfg(all(debug_assertions, feature = "std"))]
+
+use snowflake::{set_duplicate_service_id_check, ConcurrentSnowflake, Snowflake};
+
+#[test]
+#[should_panic(expected = "already uses service id 0")]
+fn test_two_live_generators_with_same_service_id_panic() {
+    set_duplicate_service_id_check(true);
+    let _first = Snowflake::new(0).unwrap();
+    let _second = Snowflake::new(0).unwrap();
+}
+
+#[test]
+fn test_service_id_can_be_reused_once_dropped() {
+    set_duplicate_service_id_check(true);
+    drop(Snowflake::new(1).unwrap());
+    let _second = Snowflake::new(1).unwrap();
+}
+
+#[test]
+fn test_shared_and_distinct_generators_dont_panic() {
+    set_duplicate_service_id_check(true);
+    let concurrent = ConcurrentSnowflake::new(2).unwrap();
+    let _clone = concurrent.clone();
+    let _other = Snowflake::new(3).unwrap();
+}
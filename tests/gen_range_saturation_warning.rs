@@ -0,0 +1,29 @@
+// runs in its own process, since the saturation warning fires once per process
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use snowflake::{sequence_saturation_warned, MockClock, Snowflake, SnowflakeLayout};
+
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+fn count_warning() {
+    WARNINGS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[test]
+fn test_gen_range_past_90_percent_fires_the_saturation_hook() {
+    let mut snowflake = Snowflake::builder(7)
+        .layout(SnowflakeLayout::DISCORD)
+        .clock(MockClock::new(Duration::from_millis(1_000)))
+        .saturation_hook(count_warning)
+        .build()
+        .unwrap();
+
+    // 90% of the 4096 ids of a millisecond is 3686
+    snowflake.gen_range(3_000).unwrap();
+    assert!(!sequence_saturation_warned());
+    snowflake.gen_range(1_000).unwrap();
+    assert!(sequence_saturation_warned());
+    assert_eq!(WARNINGS.load(Ordering::Relaxed), 1);
+}
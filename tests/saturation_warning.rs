@@ -0,0 +1,41 @@
+// runs in its own process, since the saturation warning fires once per process
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use snowflake::{sequence_saturation_warned, MockClock, Snowflake};
+
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+fn count_warning() {
+    WARNINGS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[test]
+fn test_saturation_hook_fires_once_per_process() {
+    let clock = MockClock::new(Duration::from_millis(1_000));
+    let mut first = Snowflake::builder(0)
+        .clock(clock.clone())
+        .saturation_hook(count_warning)
+        .build()
+        .unwrap();
+    let mut second = Snowflake::builder(1)
+        .clock(clock.clone())
+        .saturation_hook(count_warning)
+        .build()
+        .unwrap();
+
+    for _ in 0..100_000 {
+        first.gen().unwrap();
+    }
+    assert!(!sequence_saturation_warned());
+    assert_eq!(WARNINGS.load(Ordering::Relaxed), 0);
+
+    // saturate three milliseconds on each generator
+    for _ in 0..3 * 131_072 {
+        first.gen().unwrap();
+        second.gen().unwrap();
+    }
+    assert!(sequence_saturation_warned());
+    assert_eq!(WARNINGS.load(Ordering::Relaxed), 1);
+}